@@ -0,0 +1,150 @@
+use std::time::Instant;
+
+use audio_sim::wave_simulator::*;
+
+const STEPS: usize = 200;
+
+// milliseconds per step
+fn time<T: Scalar>(settings: Settings) -> f64 {
+    let mut wave_simulator = WaveSimulator::<T>::with_settings(settings).unwrap();
+    wave_simulator.add_gauss(
+        settings.width as f32 / 2.0,
+        settings.height as f32 / 2.0,
+        4.0,
+        1.0,
+    );
+    wave_simulator.run(10).unwrap();
+
+    let start = Instant::now();
+    wave_simulator.run(STEPS).unwrap();
+    start.elapsed().as_secs_f64() * 1000.0 / STEPS as f64
+}
+
+fn time3d(settings: Settings3D) -> f64 {
+    let mut wave_simulator = WaveSimulator3D::<f32>::with_settings(settings).unwrap();
+    wave_simulator.add_gauss(
+        settings.width as f32 / 2.0,
+        settings.height as f32 / 2.0,
+        settings.depth as f32 / 2.0,
+        4.0,
+        1.0,
+    );
+    wave_simulator.run(10).unwrap();
+
+    let start = Instant::now();
+    wave_simulator.run(STEPS).unwrap();
+    start.elapsed().as_secs_f64() * 1000.0 / STEPS as f64
+}
+
+fn main() {
+    // a whole step with the defaults, as main.rs runs it but on one thread. see the readme
+    // for what it took before the fields were rotated.
+    for &size in [96, 256, 512].iter() {
+        let settings = Settings {
+            num_threads: 1,
+            ..Settings::new(size, size)
+        };
+        let watched = time::<f32>(settings);
+        let unwatched = time::<f32>(Settings {
+            watch: None,
+            ..settings
+        });
+
+        println!(
+            "{:>4} {:<12} step   {:>7.3} ms  unwatched {:>7.3} ms",
+            size, "default", watched, unwatched
+        );
+    }
+
+    for &size in [256, 512, 1024].iter() {
+        for &stencil in [Stencil::FivePoint, Stencil::NinePoint, Stencil::FourthOrder].iter() {
+            let settings = Settings {
+                stencil: stencil,
+                backend: Backend::Serial,
+                ..Settings::new(size, size)
+            };
+            let scalar = time::<f32>(Settings {
+                simd: false,
+                ..settings
+            });
+            let vector = time::<f32>(settings);
+
+            println!(
+                "{:>4} {:<12} scalar {:>7.3} ms  simd {:>7.3} ms  {:.2}x",
+                size,
+                format!("{:?}", stencil),
+                scalar,
+                vector,
+                scalar / vector
+            );
+        }
+    }
+
+    // grids far larger than the cache
+    for &size in [2048, 4096].iter() {
+        for &backend in [Backend::Serial, Backend::Threaded].iter() {
+            let settings = Settings {
+                backend: backend,
+                ..Settings::new(size, size)
+            };
+            let plain = time::<f32>(settings);
+            let tiled = time::<f32>(Settings {
+                tiling: Some(Tiling::for_width(size)),
+                ..settings
+            });
+
+            println!(
+                "{:>4} {:<12} plain  {:>7.3} ms  tiled {:>7.3} ms  {:.2}x",
+                size,
+                format!("{:?}", backend),
+                plain,
+                tiled,
+                plain / tiled
+            );
+        }
+    }
+
+    // twice the bytes per cell, half the lanes
+    for &size in [256, 512, 1024].iter() {
+        let settings = Settings {
+            backend: Backend::Serial,
+            ..Settings::new(size, size)
+        };
+        let single = time::<f32>(settings);
+        let double = time::<f64>(settings);
+
+        println!(
+            "{:>4} {:<12} f32    {:>7.3} ms  f64   {:>7.3} ms  {:.2}x",
+            size,
+            "FivePoint",
+            single,
+            double,
+            double / single
+        );
+    }
+
+    // volumes, one slab per thread
+    for &size in [64, 128].iter() {
+        for &stencil in [Stencil3D::SevenPoint, Stencil3D::FourthOrder].iter() {
+            let settings = Settings3D {
+                stencil: stencil,
+                num_threads: 1,
+                ..Settings3D::new(size, size, size)
+            };
+            let scalar = time3d(Settings3D {
+                simd: false,
+                ..settings
+            });
+            let vector = time3d(settings);
+
+            println!(
+                "{:>4} {:<12} scalar {:>7.3} ms  simd {:>7.3} ms  {:.2}x",
+                size,
+                format!("{:?}", stencil),
+                scalar,
+                vector,
+                scalar / vector
+            );
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt;
+use std::io;
+use std::sync::mpsc::SendError;
+use std::sync::PoisonError;
+
+use crate::wave_simulator::{Instability, WorkerPanic};
+
+// everything the library can fail with
+#[derive(Debug)]
+pub enum Error {
+    // settings or a builder that can't make something that works, says what is wrong
+    Config(String),
+    // a thread panicked while holding a lock
+    Poisoned,
+    // a worker panicked, the field is garbage from here on
+    Worker(WorkerPanic),
+    // the field blew up, see Settings::watch
+    Unstable(Instability),
+    // whoever was on the other end of a channel is gone
+    Disconnected,
+    // this domain of an instrument failed to step, the ones before it did. the instrument
+    // refuses to step until it is reset.
+    OutOfStep(usize),
+    // the window couldn't be made or drawn, says what glium or imgui complained about
+    Gui(String),
+    Io(io::Error),
+    // a wav couldn't be read or written
+    Format(hound::Error),
+}
+
+impl Error {
+    pub(crate) fn config(reason: &str) -> Error {
+        Error::Config(reason.to_owned())
+    }
+
+    pub(crate) fn gui<E: fmt::Display>(error: E) -> Error {
+        Error::Gui(error.to_string())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Config(reason) => write!(f, "invalid configuration: {}", reason),
+            Error::Poisoned => write!(f, "a lock was poisoned by a panicking thread"),
+            Error::Worker(panic) => panic.fmt(f),
+            Error::Unstable(instability) => instability.fmt(f),
+            Error::Disconnected => write!(f, "the other end of the channel is gone"),
+            Error::OutOfStep(domain) => write!(
+                f,
+                "domain {} failed to step, the instrument is out of step until it is reset",
+                domain
+            ),
+            Error::Gui(reason) => write!(f, "gui failed: {}", reason),
+            Error::Io(error) => error.fmt(f),
+            Error::Format(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Worker(panic) => Some(panic),
+            Error::Unstable(instability) => Some(instability),
+            Error::Io(error) => Some(error),
+            Error::Format(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<WorkerPanic> for Error {
+    fn from(panic: WorkerPanic) -> Error {
+        Error::Worker(panic)
+    }
+}
+
+impl From<Instability> for Error {
+    fn from(instability: Instability) -> Error {
+        Error::Unstable(instability)
+    }
+}
+
+impl<T> From<PoisonError<T>> for Error {
+    fn from(_: PoisonError<T>) -> Error {
+        Error::Poisoned
+    }
+}
+
+impl<T> From<SendError<T>> for Error {
+    fn from(_: SendError<T>) -> Error {
+        Error::Disconnected
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Error {
+        Error::Io(error)
+    }
+}
+
+// hound wraps the io errors it runs into
+impl From<hound::Error> for Error {
+    fn from(error: hound::Error) -> Error {
+        match error {
+            hound::Error::IoError(error) => Error::Io(error),
+            error => Error::Format(error),
+        }
+    }
+}
@@ -1,5 +1,3 @@
-use crate::SIZE;
-
 use std::rc::Rc;
 use std::sync::*;
 use std::time::*;
@@ -14,9 +12,18 @@ use imgui::*;
 use imgui_glium_renderer::*;
 use imgui_winit_support::*;
 
+use crate::Error;
+
 pub type Textures = imgui::Textures<Texture2d>;
 
-pub fn run<F>(title: String, mem: Arc<Mutex<Vec<f32>>>, mut run_ui: F)
+// runs until the window is closed, whatever glium or imgui fail with comes back as Error::Gui
+pub fn run<F>(
+    title: String,
+    columns: usize,
+    rows: usize,
+    mem: Arc<Mutex<Vec<f32>>>,
+    mut run_ui: F,
+) -> Result<(), Error>
 where
     F: FnMut(&mut bool, &mut Ui) -> bool,
 {
@@ -30,13 +37,13 @@ where
 
     let cb = glutin::ContextBuilder::new().with_vsync(true);
 
-    let display = Display::new(wb, cb, &events_loop).unwrap();
+    let display = Display::new(wb, cb, &events_loop).map_err(Error::gui)?;
 
     let mic_image = image::load(
         std::io::Cursor::new(&include_bytes!("../resources/images/mic.png")[..]),
         image::PNG,
     )
-    .unwrap()
+    .map_err(Error::gui)?
     .to_rgba();
 
     let mic_image_dimensions = mic_image.dimensions();
@@ -68,7 +75,7 @@ where
 
     imgui.io_mut().font_global_scale = (1.0 / hidpi_factor) as f32;
 
-    let mut renderer = Renderer::init(&mut imgui, &display).unwrap();
+    let mut renderer = Renderer::init(&mut imgui, &display).map_err(Error::gui)?;
 
     //imgui_winit_support::configure_keys(&mut imgui);
 
@@ -101,7 +108,7 @@ where
         position: [1.0, -1.0],
     };
     let shape = vec![v1, v2, v3, v4, v5, v6];
-    let vertex_buffer = glium::VertexBuffer::new(&display, &shape).unwrap();
+    let vertex_buffer = glium::VertexBuffer::new(&display, &shape).map_err(Error::gui)?;
     let indices = glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList);
 
     let vertex_shader_src = format!(
@@ -118,6 +125,9 @@ where
     "#
     );
 
+    // values are packed into vec4s, so pad the buffer when the grid is not a multiple of 4
+    let len = (columns * rows + 3) / 4;
+
     let fragment_shader_src = format!(
         r#"
         #version 430
@@ -132,11 +142,11 @@ where
 
         void main()
         {{
-            uint x = uint((gl_FragCoord.x / width) * float({size}));
-            uint y = uint((gl_FragCoord.y / height) * float({size}));
+            uint x = uint((gl_FragCoord.x / width) * float({columns}));
+            uint y = uint((gl_FragCoord.y / height) * float({rows}));
 
-            uint i = (x+y*{size})/4;
-            uint j = (x+y*{size})%4;
+            uint i = (x+y*{columns})/4;
+            uint j = (x+y*{columns})%4;
 
             vec4 value_vec = values[i];
 
@@ -160,8 +170,9 @@ where
             color = vec4(value/20.0, value/5.0, value, 1.0);
         }}
     "#,
-        len = SIZE * SIZE,
-        size = SIZE
+        len = len,
+        columns = columns,
+        rows = rows
     );
 
     let program = glium::Program::from_source(
@@ -170,7 +181,7 @@ where
         fragment_shader_src.as_str(),
         None,
     )
-    .unwrap();
+    .map_err(Error::gui)?;
 
     struct Data {
         values: [[f32; 4]],
@@ -180,7 +191,7 @@ where
     implement_uniform_block!(Data, values);
 
     let mut buffer: glium::uniforms::UniformBuffer<Data> =
-        glium::uniforms::UniformBuffer::empty_unsized(&display, 4 * SIZE * SIZE).unwrap();
+        glium::uniforms::UniformBuffer::empty_unsized(&display, 4 * 4 * len).map_err(Error::gui)?;
 
     while run {
         events_loop.poll_events(|event| {
@@ -200,7 +211,7 @@ where
 
         let now = Instant::now();
 
-        platform.prepare_frame(io, &window).unwrap();
+        platform.prepare_frame(io, &window).map_err(Error::gui)?;
         last_frame = io.update_delta_time(last_frame);
         let mut ui = imgui.frame();
 
@@ -211,28 +222,35 @@ where
         //quit = true;
         //}
 
-        let logical_size = window.get_inner_size().unwrap();
+        let logical_size = window
+            .get_inner_size()
+            .ok_or_else(|| Error::gui("the window is gone"))?;
 
-        let mut target = display.draw();
-        target.clear_color(0.0, 1.0, 1.0, 1.0);
         {
             let vec: Vec<f32>;
             {
-                let m = mem.lock().unwrap();
+                let m = mem.lock()?;
                 vec = m.clone();
             }
 
             let mut mapping = buffer.map();
             for (i, val) in mapping.values.iter_mut().enumerate() {
-                *val = [vec[i * 4], vec[i * 4 + 1], vec[i * 4 + 2], vec[i * 4 + 3]];
+                let value = |j: usize| vec.get(i * 4 + j).cloned().unwrap_or(0.0);
+                *val = [value(0), value(1), value(2), value(3)];
             }
         }
 
-        target.draw(&vertex_buffer, &indices, &program, &uniform!{MyBlock: &*buffer, width: logical_size.width as f32, height: logical_size.height as f32}, &Default::default()).unwrap();
+        let mut target = display.draw();
+        target.clear_color(0.0, 1.0, 1.0, 1.0);
+
+        let drawn = target.draw(&vertex_buffer, &indices, &program, &uniform!{MyBlock: &*buffer, width: logical_size.width as f32, height: logical_size.height as f32}, &Default::default()).map_err(Error::gui);
 
         platform.prepare_render(&ui, &window);
         let draw_data = ui.render();
-        renderer.render(&mut target, draw_data).unwrap();
-        target.finish().unwrap();
+        let rendered = renderer.render(&mut target, draw_data).map_err(Error::gui);
+        // glium panics on dropping a frame that isn't finished, so finish it before failing
+        let finished = target.finish().map_err(Error::gui);
+        drawn.and(rendered).and(finished)?;
     }
+    Ok(())
 }
@@ -1,10 +1,13 @@
 #[macro_use]
 extern crate glium;
 
+pub mod error;
 pub mod gui;
 pub mod oscillator;
 pub mod wave_simulator;
 
-pub const SIZE: usize = 96;
-pub const NUM_THREADS: usize = 2;
+pub use error::Error;
+
+pub const WIDTH: usize = 96;
+pub const HEIGHT: usize = 96;
 pub const SLEEP_TIME: u64 = 0;
@@ -1,8 +1,9 @@
 use audio_sim::oscillator;
 use audio_sim::wave_simulator;
 use audio_sim::wave_simulator::*;
-use audio_sim::SIZE;
+use audio_sim::HEIGHT;
 use audio_sim::SLEEP_TIME;
+use audio_sim::WIDTH;
 
 use std::sync::mpsc::*;
 use std::sync::*;
@@ -11,132 +12,383 @@ use std::time::Instant;
 
 use hound;
 
-fn main() -> std::io::Result<()> {
-    let mut wave_simulator = WaveSimulator::new(SIZE).unwrap();
-    wave_simulator.add_gauss(50.0, 50.0, 1.0, 1.0);
+fn main() -> Result<(), audio_sim::Error> {
+    // usage: audio_sim [width] [height] [depth] [f32|f64], a depth makes it a volume.
+    // audio_sim plate [width] [height] [f32|f64] strikes a steel plate instead of the air.
+    // audio_sim string [length] [f32|f64] plucks a string and only writes the wav.
+    let mut args = std::env::args().skip(1).peekable();
+    let mode = match args.peek().map(String::as_str) {
+        Some("plate") | Some("string") => args.next(),
+        _ => None,
+    };
+    if mode.as_deref() == Some("string") {
+        let length = args.next().and_then(|a| a.parse().ok()).unwrap_or(WIDTH);
+        return match args.next().as_deref() {
+            Some("f64") => simulate1d::<f64>(length),
+            _ => simulate1d::<f32>(length),
+        };
+    }
+
+    let formulation = match mode.as_deref() {
+        Some("plate") => Formulation::Plate { loss: 0.0005 },
+        _ => Formulation::SecondOrder,
+    };
+    let width = args.next().and_then(|a| a.parse().ok()).unwrap_or(WIDTH);
+    let height = args.next().and_then(|a| a.parse().ok()).unwrap_or(HEIGHT);
+    let mut next = args.next();
+    let depth = next.as_ref().and_then(|a| a.parse::<usize>().ok());
+    if depth.is_some() {
+        next = args.next();
+    }
+
+    match (depth, next.as_deref()) {
+        (Some(depth), Some("f64")) => simulate3d::<f64>(width, height, depth),
+        (Some(depth), _) => simulate3d::<f32>(width, height, depth),
+        (None, Some("f64")) => simulate::<f64>(width, height, formulation),
+        (None, _) => simulate::<f32>(width, height, formulation),
+    }
+}
+
+// the gui and the wav only ever see f32
+fn simulate<T: Scalar>(
+    width: usize,
+    height: usize,
+    formulation: Formulation,
+) -> Result<(), audio_sim::Error> {
+    // 2 cm cells, one step per sample
+    let mut wave_simulator = WaveSimulator::builder()
+        .size(width, height)
+        .dx(0.02)
+        .dt(1.0 / 44100.0)
+        .formulation(formulation)
+        .precision::<T>()
+        .build()?;
+    let physics = wave_simulator.physics();
+    let max_speed = wave_simulator.max_stable_speed();
+    let everywhere = wave_simulator::Region::Rect {
+        x: 0,
+        y: 0,
+        width: width,
+        height: height,
+    };
+    wave_simulator.order(match formulation {
+        // 1 mm of steel, held at the edges
+        Formulation::Plate { .. } => wave_simulator::Order::Fill(
+            everywhere,
+            (Formulation::plate_stiffness(2e11, 0.3, 7800.0, 0.001), 1.0),
+        ),
+        _ => wave_simulator::Order::FillMaterial(everywhere, Material::air(20.0)),
+    });
+    wave_simulator.add_gauss(width as f32 / 2.0, height as f32 / 2.0, 1.0, 1.0);
 
-    let mem_gui = Arc::new(Mutex::new(vec![0.0; SIZE * SIZE]));
+    let mem_gui = Arc::new(Mutex::new(vec![0.0; width * height]));
     let mem_copy = Arc::clone(&mem_gui);
 
     let (tx, rx) = mpsc::channel();
 
     thread::spawn(move || {
-        gui(mem_copy, tx);
+        if let Err(e) = gui(width, height, 1, max_speed, mem_copy, tx) {
+            println!("{}", e);
+        }
     });
 
-    let mut writer = get_writer();
+    let mut writer = get_writer(physics.sample_rate)?;
+    // samples owed to the wav, steps and samples need not line up
+    let mut samples = 0.0;
 
     let mut now = Instant::now();
     let mut mic_l_pos = [0, 0];
     let mut mic_r_pos = [0, 0];
-    let tx_order_vec = wave_simulator.tx_order.clone();
-    for (t, space) in wave_simulator.enumerate() {
-        let mut order = None;
-        if let Ok(o) = rx.try_recv() {
+    for t in 0usize.. {
+        if let Err(e) = wave_simulator.step() {
+            println!("{}", e);
+            break;
+        }
+
+        let mut quit = false;
+        loop {
+            let o = match rx.try_recv() {
+                Ok(o) => o,
+                // the window is closed or failed, nobody is left to quit
+                Err(TryRecvError::Disconnected) => {
+                    quit = true;
+                    break;
+                }
+                Err(TryRecvError::Empty) => break,
+            };
             match o {
-                Order::Drop(x, y, f) => order = Some(Order::Drop(x, y, f)),
-                Order::MoveMic(mic, pos) => match mic {
-                    Mic::Left => mic_l_pos = pos,
-                    Mic::Right => mic_r_pos = pos,
-                },
-                Order::WaveSim(ws_order) => match ws_order {
-                    wave_simulator::Order::Change(Parameter::PropagationRatio(x, y, value)) => {
-                        order = Some(o.clone())
-                    }
-                    wave_simulator::Order::Change(Parameter::DumpingRatio(x, y, value)) => {
-                        order = Some(o.clone())
+                Order::Drop(x, y, f) => wave_simulator.field_mut()[x + y * width] += T::from_f32(f),
+                Order::MoveMic(mic, pos) => {
+                    let pos = [pos[0].min(width - 1), pos[1].min(height - 1)];
+                    match mic {
+                        Mic::Left => mic_l_pos = pos,
+                        Mic::Right => mic_r_pos = pos,
                     }
-                    _ => tx_order_vec.iter().for_each(|tx| {
-                        tx.send(ws_order.clone()).unwrap();
-                    }),
-                },
-                Order::Quit => break,
-                _ => {}
+                }
+                // applied by the simulator before the next step
+                Order::WaveSim(ws_order) => wave_simulator.order(ws_order),
+                Order::Slice(_) => {}
+                Order::Quit => quit = true,
+            }
+        }
+        if quit {
+            break;
+        }
+
+        let mic_l = wave_simulator.get(mic_l_pos[0], mic_l_pos[1]).to_f32();
+        let mic_r = wave_simulator.get(mic_r_pos[0], mic_r_pos[1]).to_f32();
+
+        record(&mut writer, &mut samples, &physics, [mic_l, mic_r])?;
+
+        if now.elapsed().as_millis() > 10 {
+            let mem_throw = picture(&wave_simulator.snapshot(), max_speed);
+            {
+                let mut m = mem_gui.lock()?;
+                *m = mem_throw;
+                m[mic_l_pos[0] as usize + mic_l_pos[1] as usize * width] = 100.0;
+                m[mic_r_pos[0] as usize + mic_r_pos[1] as usize * width] = 100.0;
             }
+            now = Instant::now();
+        }
+
+        //println!("{} ms", now.elapsed().as_millis());
+
+        // every 100 ms of simulated time
+        if t % (0.1 / physics.dt).round().max(1.0) as usize == 0 {
+            println!("{} s", t as f32 * physics.dt);
+        }
+
+        thread::sleep(std::time::Duration::from_millis(SLEEP_TIME));
+    }
+
+    // the header only gets the length once it is finalized
+    writer.finalize()?;
+    Ok(())
+}
+
+// a room of air, shown a z slice at a time
+fn simulate3d<T: Scalar>(
+    width: usize,
+    height: usize,
+    depth: usize,
+) -> Result<(), audio_sim::Error> {
+    let mut wave_simulator = WaveSimulator3D::<T>::with_settings(Settings3D {
+        // 2 cm cells, one step per sample
+        physics: Physics {
+            dx: 0.02,
+            dt: 1.0 / 44100.0,
+            ..Physics::default()
+        },
+        ..Settings3D::new(width, height, depth)
+    })?;
+    let physics = wave_simulator.physics();
+    // the cells start as air
+    let max_speed = wave_simulator.max_stable_speed();
+    wave_simulator.add_gauss(
+        width as f32 / 2.0,
+        height as f32 / 2.0,
+        depth as f32 / 2.0,
+        1.0,
+        1.0,
+    );
+
+    let mem_gui = Arc::new(Mutex::new(vec![0.0; width * height]));
+    let mem_copy = Arc::clone(&mem_gui);
+
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        if let Err(e) = gui(width, height, depth, max_speed, mem_copy, tx) {
+            println!("{}", e);
         }
+    });
 
-        let mut sp;
-        {
-            let mut s = space.lock().unwrap();
-            sp = s.clone();
+    let mut writer = get_writer(physics.sample_rate)?;
+    let mut samples = 0.0;
 
-            if let Some(o) = order {
-                match o {
-                    Order::Drop(x, y, f) => {
-                        s.space[x + y * SIZE] += f;
+    let mut now = Instant::now();
+    let mut mic_l_pos = [0, 0, 0];
+    let mut mic_r_pos = [0, 0, 0];
+    let mut slice = 0;
+    for t in 0usize.. {
+        if let Err(e) = wave_simulator.step() {
+            println!("{}", e);
+            break;
+        }
+
+        let mut quit = false;
+        loop {
+            let o = match rx.try_recv() {
+                Ok(o) => o,
+                // the window is closed or failed, nobody is left to quit
+                Err(TryRecvError::Disconnected) => {
+                    quit = true;
+                    break;
+                }
+                Err(TryRecvError::Empty) => break,
+            };
+            match o {
+                Order::Drop(x, y, f) => {
+                    wave_simulator.field_mut()[x + y * width + slice * width * height] +=
+                        T::from_f32(f)
+                }
+                Order::MoveMic(mic, pos) => {
+                    let pos = [
+                        pos[0].min(width - 1),
+                        pos[1].min(height - 1),
+                        pos[2].min(depth - 1),
+                    ];
+                    match mic {
+                        Mic::Left => mic_l_pos = pos,
+                        Mic::Right => mic_r_pos = pos,
+                    }
+                }
+                Order::Slice(z) => slice = z.min(depth - 1),
+                // spec edits land on the slice shown
+                Order::WaveSim(ws_order) => {
+                    if let Err(e) = wave_simulator.apply(slice, ws_order) {
+                        println!("{}", e);
                     }
-                    Order::WaveSim(ws_order) => match ws_order {
-                        wave_simulator::Order::Change(Parameter::PropagationRatio(x, y, value)) => {
-                            s.space_spec[x + y * SIZE].0 = value;
-                            s.space[x + y * SIZE] = 0.0;
-                        }
-                        wave_simulator::Order::Change(Parameter::DumpingRatio(x, y, value)) => {
-                            s.space_spec[x + y * SIZE].1 = value;
-                            s.space[x + y * SIZE] = 0.0;
-                        }
-                    },
-                    _ => (),
                 }
+                Order::Quit => quit = true,
             }
         }
+        if quit {
+            break;
+        }
 
-        let amplitude = std::i16::MAX as f32 / 10.0;
-        writer
-            .write_sample(
-                (sp.space[mic_l_pos[0] as usize + mic_l_pos[1] as usize * SIZE] * amplitude) as i16,
-            )
-            .unwrap();
-        writer
-            .write_sample(
-                (sp.space[mic_r_pos[0] as usize + mic_r_pos[1] as usize * SIZE] * amplitude) as i16,
-            )
-            .unwrap();
+        let mic_l = wave_simulator
+            .get(mic_l_pos[0], mic_l_pos[1], mic_l_pos[2])
+            .to_f32();
+        let mic_r = wave_simulator
+            .get(mic_r_pos[0], mic_r_pos[1], mic_r_pos[2])
+            .to_f32();
+
+        record(&mut writer, &mut samples, &physics, [mic_l, mic_r])?;
 
         if now.elapsed().as_millis() > 10 {
-            let mem_throw = sp
-                .space
-                .as_slice()
-                .iter()
-                .enumerate()
-                .map(|(i, v)| v / 20.0 + (1.0 - sp.space_spec[i].0) + sp.space_spec[i].1 / 2.0)
-                .collect::<Vec<f32>>();
+            let mem_throw = picture(&wave_simulator.slice(Axis::Z, slice), max_speed);
             {
-                let mut m = mem_gui.lock().unwrap();
+                let mut m = mem_gui.lock()?;
                 *m = mem_throw;
-                m[mic_l_pos[0] as usize + mic_l_pos[1] as usize * SIZE] = 100.0;
-                m[mic_r_pos[0] as usize + mic_r_pos[1] as usize * SIZE] = 100.0;
+                // mics are only drawn on their own slice
+                for pos in [mic_l_pos, mic_r_pos].iter() {
+                    if pos[2] == slice {
+                        m[pos[0] + pos[1] * width] = 100.0;
+                    }
+                }
             }
             now = Instant::now();
         }
 
-        //println!("{} ms", now.elapsed().as_millis());
-
-        // every 100 ms
-        if t % 4410 == 0 {
-            println!("{} s", t as f32 / 44100.0);
+        if t % (0.1 / physics.dt).round().max(1.0) as usize == 0 {
+            println!("{} s", t as f32 * physics.dt);
         }
 
         thread::sleep(std::time::Duration::from_millis(SLEEP_TIME));
     }
 
+    writer.finalize()?;
+    Ok(())
+}
+
+// a guitar's a string, 65 cm at 110 Hz, plucked and listened to for two seconds
+fn simulate1d<T: Scalar>(length: usize) -> Result<(), audio_sim::Error> {
+    let mut string = WaveSimulator1D::<T>::with_settings(Settings1D {
+        physics: Physics::audio(0.65 / length as f32, 44100),
+        ..Settings1D::new(length)
+    })?;
+    let physics = string.physics();
+    // 2 * 0.65 m * 110 Hz = sqrt(133 N / 6.5 g/m)
+    let (tension, linear_density) = (133.0, 0.0065);
+    if (tension / linear_density).sqrt() > string.max_stable_speed() {
+        return Err(audio_sim::Error::Config(format!(
+            "{} samples are too many for a string at {} Hz",
+            length, physics.sample_rate
+        )));
+    }
+    string.set_tension(tension, linear_density);
+    string.pluck(length as f32 / 5.0, 1.0);
+
+    let mut writer = get_writer(physics.sample_rate)?;
+    let mut samples = 0.0;
+
+    // pickups, one per channel
+    let (pickup_l, pickup_r) = (length / 8, length / 3);
+    let steps = (2.0 / physics.dt) as usize;
+    for t in 0..steps {
+        if let Err(e) = string.step() {
+            println!("{}", e);
+            break;
+        }
+
+        let mics = [string.get(pickup_l).to_f32(), string.get(pickup_r).to_f32()];
+        record(&mut writer, &mut samples, &physics, mics)?;
+
+        if t % (0.1 / physics.dt).round().max(1.0) as usize == 0 {
+            println!("{} s", t as f32 * physics.dt);
+        }
+    }
+
+    writer.finalize()?;
     Ok(())
 }
 
+// writes the mics to the wav as often as the sample rate asks, `samples` carries what is
+// owed over from step to step
+fn record(
+    writer: &mut Writer,
+    samples: &mut f64,
+    physics: &Physics,
+    mics: [f32; 2],
+) -> Result<(), audio_sim::Error> {
+    let amplitude = std::i16::MAX as f32 / 10.0;
+    *samples += physics.samples_per_step();
+    while *samples >= 1.0 {
+        for mic in mics.iter() {
+            writer.write_sample((mic * amplitude) as i16)?;
+        }
+        *samples -= 1.0;
+    }
+    Ok(())
+}
+
+// what the gui draws for every cell, the field over the spec
+fn picture<T: Scalar>(space: &Space<T>, max_speed: f32) -> Vec<f32> {
+    space
+        .space
+        .iter()
+        .zip(space.space_spec.iter())
+        .map(|(v, &(c, k))| v.to_f32() / 20.0 + (1.0 - c.to_f32() / max_speed) + k.to_f32() / 100.0)
+        .collect()
+}
+
 #[derive(Copy, Clone, Debug)]
 enum Mic {
     Left,
     Right,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 enum Order {
     Drop(usize, usize, f32),
-    MoveMic(Mic, [usize; 2]),
+    // x, y and z, a plane only has z = 0
+    MoveMic(Mic, [usize; 3]),
+    // plane of the volume the gui shows
+    Slice(usize),
     WaveSim(wave_simulator::Order),
     Quit,
 }
 
-fn gui(mem: Arc<Mutex<Vec<f32>>>, tx: Sender<Order>) {
+// shows one z slice of the field, `depth` is 1 for a plane
+fn gui(
+    width: usize,
+    height: usize,
+    depth: usize,
+    max_speed: f32,
+    mem: Arc<Mutex<Vec<f32>>>,
+    tx: Sender<Order>,
+) -> Result<(), audio_sim::Error> {
     use audio_sim::gui;
     use imgui::*;
 
@@ -145,30 +397,47 @@ fn gui(mem: Arc<Mutex<Vec<f32>>>, tx: Sender<Order>) {
     let mut mic_l_pos: [i32; 2] = [0, 0];
     let mut mic_r_pos: [i32; 2] = [0, 0];
     let mut oscillate: bool = false;
-    let mut propagration_ratio: f32 = 0.2;
-    let mut dumping_ratio: f32 = 0.1;
+    // the air's, or as stiff as the plate may get
+    let mut propagration_ratio: f32 = 343.0f32.min(max_speed);
+    let mut dumping_ratio: f32 = 10.0;
     let mut mode: i32 = 0;
-
-    gui::run("Audio Simulator".to_owned(), mem, |mut run, mut ui| {
-        ui_func(
-            &mut ui,
-            &mut run,
-            tx.clone(),
-            &mut drop_pos,
-            &mut drop_f,
-            &mut mic_l_pos,
-            &mut mic_r_pos,
-            &mut oscillate,
-            &mut propagration_ratio,
-            &mut dumping_ratio,
-            &mut mode,
-        )
-    });
+    let mut slice: i32 = 0;
+
+    gui::run(
+        "Audio Simulator".to_owned(),
+        width,
+        height,
+        mem,
+        |mut run, mut ui| {
+            ui_func(
+                &mut ui,
+                &mut run,
+                tx.clone(),
+                width,
+                height,
+                depth,
+                max_speed,
+                &mut drop_pos,
+                &mut drop_f,
+                &mut mic_l_pos,
+                &mut mic_r_pos,
+                &mut oscillate,
+                &mut propagration_ratio,
+                &mut dumping_ratio,
+                &mut mode,
+                &mut slice,
+            )
+        },
+    )?;
 
     fn ui_func<'a>(
         ui: &mut Ui<'a>,
         run: &mut bool,
         tx: Sender<Order>,
+        width: usize,
+        height: usize,
+        depth: usize,
+        max_speed: f32,
         mut drop_pos: &mut [i32; 2],
         mut drop_f: &mut f32,
         mut mic_l_pos: &mut [i32; 2],
@@ -177,14 +446,16 @@ fn gui(mem: Arc<Mutex<Vec<f32>>>, tx: Sender<Order>) {
         mut propagration_ratio: &mut f32,
         mut dumping_ratio: &mut f32,
         mut mode: &mut i32,
+        mut slice: &mut i32,
     ) -> bool {
+        let max_pos = width.max(height) as i32 - 1;
         ui.window(im_str!("nanamin!!"))
             .size([500.0, 300.0], Condition::FirstUseEver)
             .build(move || {
                 ui.text(im_str!("wave!"));
                 ui.separator();
                 /*
-                ui.slider_int2(im_str!("drop pos"), &mut drop_pos, 0, SIZE as i32)
+                ui.slider_int2(im_str!("drop pos"), &mut drop_pos, 0, max_pos)
                     .build();
                     */
 
@@ -199,12 +470,11 @@ fn gui(mem: Arc<Mutex<Vec<f32>>>, tx: Sender<Order>) {
                         "drop!\n(x, y, f) : ({}, {}, {})",
                         drop_pos.0], drop_pos.1], drop_f
                     );
-                    tx.send(Order::Drop(
+                    send(&tx, run, Order::Drop(
                         drop_pos.0] as u32 as usize,
                         drop_pos.1] as u32 as usize,
                         *drop_f,
-                    ))
-                    .unwrap();
+                    ));
                 }
                 */
 
@@ -213,46 +483,43 @@ fn gui(mem: Arc<Mutex<Vec<f32>>>, tx: Sender<Order>) {
                         im_str!("propagration ratio"),
                         &mut propagration_ratio,
                         0.0,
-                        1.0,
+                        max_speed,
                     )
                     .build()
                 {
                     /*
-                    tx.send(Order::WaveSim(wave_simulator::Order::Change(
+                    send(&tx, run, Order::WaveSim(wave_simulator::Order::Change(
                         wave_simulator::Parameter::PropagationRatio(10, 10, *propagration_ratio),
-                    )))
-                    .unwrap();
+                    )));
                     */
                 }
+                // ctrl click lets a value past the slider, the simulator would refuse it
+                *propagration_ratio = propagration_ratio.max(0.0).min(max_speed);
 
                 if ui
-                    .slider_float(im_str!("dumping ratio"), &mut dumping_ratio, 0.0, 2.0)
+                    .slider_float(im_str!("dumping ratio"), &mut dumping_ratio, 0.0, 100.0)
                     .build()
                 {
                     /*
-                    tx.send(Order::WaveSim(wave_simulator::Order::Change(
+                    send(&tx, run, Order::WaveSim(wave_simulator::Order::Change(
                         wave_simulator::Parameter::DumpingRatio(10, 10, *dumping_ratio),
-                    )))
-                    .unwrap();
+                    )));
                     */
                 }
                 if ui.button(im_str!("fill spec!"), [80.0, 20.0]) {
-                    for x in 0..SIZE {
-                        for y in 0..SIZE {
-                            tx.send(Order::WaveSim(wave_simulator::Order::Change(
-                                wave_simulator::Parameter::DumpingRatio(x, y, *dumping_ratio),
-                            )))
-                            .unwrap();
-                            tx.send(Order::WaveSim(wave_simulator::Order::Change(
-                                wave_simulator::Parameter::PropagationRatio(
-                                    x,
-                                    y,
-                                    *propagration_ratio,
-                                ),
-                            )))
-                            .unwrap();
-                        }
-                    }
+                    send(
+                        &tx,
+                        run,
+                        Order::WaveSim(wave_simulator::Order::Fill(
+                            wave_simulator::Region::Rect {
+                                x: 0,
+                                y: 0,
+                                width: width,
+                                height: height,
+                            },
+                            (*propagration_ratio, *dumping_ratio),
+                        )),
+                    );
                 }
 
                 if ui.imgui().is_mouse_down(MouseButton::Left)
@@ -261,14 +528,17 @@ fn gui(mem: Arc<Mutex<Vec<f32>>>, tx: Sender<Order>) {
                 {
                     let mouse_pos = ui.imgui().mouse_pos();
                     let frame_size = ui.io().display_size;
-                    let x = (mouse_pos.0 / frame_size[0] as f32 * SIZE as f32) as u32 as usize;
-                    let y =
-                        ((1.0 - mouse_pos.1 / frame_size[1] as f32) * SIZE as f32) as u32 as usize;
-                    if x < SIZE && y < SIZE {
-                        tx.send(Order::WaveSim(wave_simulator::Order::Change(
-                            wave_simulator::Parameter::DumpingRatio(x, y, *dumping_ratio),
-                        )))
-                        .unwrap()
+                    let x = (mouse_pos.0 / frame_size[0] as f32 * width as f32) as u32 as usize;
+                    let y = ((1.0 - mouse_pos.1 / frame_size[1] as f32) * height as f32) as u32
+                        as usize;
+                    if x < width && y < height {
+                        send(
+                            &tx,
+                            run,
+                            Order::WaveSim(wave_simulator::Order::Change(
+                                wave_simulator::Parameter::DumpingRatio(x, y, *dumping_ratio),
+                            )),
+                        )
                     }
                 }
 
@@ -278,26 +548,40 @@ fn gui(mem: Arc<Mutex<Vec<f32>>>, tx: Sender<Order>) {
                 {
                     let mouse_pos = ui.imgui().mouse_pos();
                     let frame_size = ui.io().display_size;
-                    let x = (mouse_pos.0 / frame_size[0] as f32 * SIZE as f32) as u32 as usize;
-                    let y =
-                        ((1.0 - mouse_pos.1 / frame_size[1] as f32) * SIZE as f32) as u32 as usize;
-                    if x < SIZE && y < SIZE {
-                        tx.send(Order::WaveSim(wave_simulator::Order::Change(
-                            wave_simulator::Parameter::PropagationRatio(x, y, *propagration_ratio),
-                        )))
-                        .unwrap();
+                    let x = (mouse_pos.0 / frame_size[0] as f32 * width as f32) as u32 as usize;
+                    let y = ((1.0 - mouse_pos.1 / frame_size[1] as f32) * height as f32) as u32
+                        as usize;
+                    if x < width && y < height {
+                        send(
+                            &tx,
+                            run,
+                            Order::WaveSim(wave_simulator::Order::Change(
+                                wave_simulator::Parameter::PropagationRatio(
+                                    x,
+                                    y,
+                                    *propagration_ratio,
+                                ),
+                            )),
+                        );
                     }
                 }
 
                 if ui
-                    .slider_int2(im_str!("mic l pos"), &mut mic_l_pos, 0, SIZE as i32)
+                    .slider_int2(im_str!("mic l pos"), &mut mic_l_pos, 0, max_pos)
                     .build()
                 {
-                    tx.send(Order::MoveMic(
-                        Mic::Left,
-                        [mic_l_pos[0] as u32 as usize, mic_l_pos[1] as u32 as usize],
-                    ))
-                    .unwrap();
+                    send(
+                        &tx,
+                        run,
+                        Order::MoveMic(
+                            Mic::Left,
+                            [
+                                mic_l_pos[0] as u32 as usize,
+                                mic_l_pos[1] as u32 as usize,
+                                *slice as u32 as usize,
+                            ],
+                        ),
+                    );
                 }
 
                 if ui.imgui().is_mouse_down(MouseButton::Left)
@@ -306,28 +590,42 @@ fn gui(mem: Arc<Mutex<Vec<f32>>>, tx: Sender<Order>) {
                 {
                     let mouse_pos = ui.imgui().mouse_pos();
                     let frame_size = ui.io().display_size;
-                    let x = (mouse_pos.0 / frame_size[0] as f32 * SIZE as f32) as u32 as usize;
-                    let y =
-                        ((1.0 - mouse_pos.1 / frame_size[1] as f32) * SIZE as f32) as u32 as usize;
-                    if x < SIZE && y < SIZE {
+                    let x = (mouse_pos.0 / frame_size[0] as f32 * width as f32) as u32 as usize;
+                    let y = ((1.0 - mouse_pos.1 / frame_size[1] as f32) * height as f32) as u32
+                        as usize;
+                    if x < width && y < height {
                         mic_l_pos[0] = x as i32;
                         mic_l_pos[1] = y as i32;
-                        tx.send(Order::MoveMic(
-                            Mic::Left,
-                            [mic_l_pos[0] as u32 as usize, mic_l_pos[1] as u32 as usize],
-                        ))
-                        .unwrap();
+                        send(
+                            &tx,
+                            run,
+                            Order::MoveMic(
+                                Mic::Left,
+                                [
+                                    mic_l_pos[0] as u32 as usize,
+                                    mic_l_pos[1] as u32 as usize,
+                                    *slice as u32 as usize,
+                                ],
+                            ),
+                        );
                     }
                 }
                 if ui
-                    .slider_int2(im_str!("mic r pos"), &mut mic_r_pos, 0, SIZE as i32)
+                    .slider_int2(im_str!("mic r pos"), &mut mic_r_pos, 0, max_pos)
                     .build()
                 {
-                    tx.send(Order::MoveMic(
-                        Mic::Right,
-                        [mic_r_pos[0] as u32 as usize, mic_r_pos[1] as u32 as usize],
-                    ))
-                    .unwrap();
+                    send(
+                        &tx,
+                        run,
+                        Order::MoveMic(
+                            Mic::Right,
+                            [
+                                mic_r_pos[0] as u32 as usize,
+                                mic_r_pos[1] as u32 as usize,
+                                *slice as u32 as usize,
+                            ],
+                        ),
+                    );
                 }
 
                 if ui.imgui().is_mouse_down(MouseButton::Right)
@@ -336,27 +634,43 @@ fn gui(mem: Arc<Mutex<Vec<f32>>>, tx: Sender<Order>) {
                 {
                     let mouse_pos = ui.imgui().mouse_pos();
                     let frame_size = ui.io().display_size;
-                    let x = (mouse_pos.0 / frame_size[0] as f32 * SIZE as f32) as u32 as usize;
-                    let y =
-                        ((1.0 - mouse_pos.1 / frame_size[1] as f32) * SIZE as f32) as u32 as usize;
-                    if x < SIZE && y < SIZE {
+                    let x = (mouse_pos.0 / frame_size[0] as f32 * width as f32) as u32 as usize;
+                    let y = ((1.0 - mouse_pos.1 / frame_size[1] as f32) * height as f32) as u32
+                        as usize;
+                    if x < width && y < height {
                         mic_r_pos[0] = x as i32;
                         mic_r_pos[1] = y as i32;
-                        tx.send(Order::MoveMic(
-                            Mic::Right,
-                            [mic_r_pos[0] as u32 as usize, mic_r_pos[1] as u32 as usize],
-                        ))
-                        .unwrap();
+                        send(
+                            &tx,
+                            run,
+                            Order::MoveMic(
+                                Mic::Right,
+                                [
+                                    mic_r_pos[0] as u32 as usize,
+                                    mic_r_pos[1] as u32 as usize,
+                                    *slice as u32 as usize,
+                                ],
+                            ),
+                        );
                     }
                 }
 
+                // mics placed from now on are put on the slice shown
+                if depth > 1
+                    && ui
+                        .slider_int(im_str!("slice"), &mut slice, 0, depth as i32 - 1)
+                        .build()
+                {
+                    send(&tx, run, Order::Slice(*slice as u32 as usize));
+                }
+
                 ui.radio_button(im_str!("normal mode!"), mode, 0);
                 ui.radio_button(im_str!("spec mode!"), mode, 1);
 
                 if ui.button(im_str!("quit!"), [80.0, 20.0]) {
                     println!("quit!");
                     *run = false;
-                    tx.send(Order::Quit).unwrap();
+                    send(&tx, run, Order::Quit);
                 }
                 if ui.imgui().is_mouse_clicked(MouseButton::Middle) {
                     *oscillate = !oscillate.clone();
@@ -365,11 +679,11 @@ fn gui(mem: Arc<Mutex<Vec<f32>>>, tx: Sender<Order>) {
                 if *oscillate {
                     let mouse_pos = ui.imgui().mouse_pos();
                     let frame_size = ui.io().display_size;
-                    let x = (mouse_pos.0 / frame_size[0] as f32 * SIZE as f32) as u32 as usize;
-                    let y =
-                        ((1.0 - mouse_pos.1 / frame_size[1] as f32) * SIZE as f32) as u32 as usize;
-                    if x < SIZE && y < SIZE {
-                        tx.send(Order::Drop(x, y, *drop_f)).unwrap();
+                    let x = (mouse_pos.0 / frame_size[0] as f32 * width as f32) as u32 as usize;
+                    let y = ((1.0 - mouse_pos.1 / frame_size[1] as f32) * height as f32) as u32
+                        as usize;
+                    if x < width && y < height {
+                        send(&tx, run, Order::Drop(x, y, *drop_f));
                     }
                 }
 
@@ -378,14 +692,25 @@ fn gui(mem: Arc<Mutex<Vec<f32>>>, tx: Sender<Order>) {
             });
         true
     }
+
+    // a send only fails once the simulation has stopped, the window goes with it
+    fn send(tx: &Sender<Order>, run: &mut bool, order: Order) {
+        if tx.send(order).is_err() {
+            *run = false;
+        }
+    }
+
+    Ok(())
 }
 
-fn get_writer() -> hound::WavWriter<std::io::BufWriter<std::fs::File>> {
+type Writer = hound::WavWriter<std::io::BufWriter<std::fs::File>>;
+
+fn get_writer(sample_rate: u32) -> Result<Writer, audio_sim::Error> {
     let spec = hound::WavSpec {
         channels: 2,
-        sample_rate: 44100,
+        sample_rate: sample_rate,
         bits_per_sample: 16,
         sample_format: hound::SampleFormat::Int,
     };
-    hound::WavWriter::create("mic.wav", spec).unwrap()
+    Ok(hound::WavWriter::create("mic.wav", spec)?)
 }
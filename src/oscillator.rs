@@ -1,5 +1,7 @@
 use std::iter::Iterator;
 
+use crate::Error;
+
 #[derive(Clone)]
 pub struct Oscillator {
     frequency: f32,
@@ -46,12 +48,22 @@ impl OscillatorBuilder {
         }
     }
 
-    pub fn build(&self) -> Oscillator {
-        Oscillator {
-            frequency: self.frequency.unwrap(),
-            sampling_rate: self.sampling_rate.unwrap(),
-            index: self.index.unwrap(),
+    // every field has to be set, and the sampling rate can't be 0
+    pub fn build(&self) -> Result<Oscillator, Error> {
+        let missing = |field: &str| Error::Config(format!("the oscillator has no {}", field));
+
+        let sampling_rate = self.sampling_rate.ok_or_else(|| missing("sampling rate"))?;
+        if sampling_rate == 0 {
+            return Err(Error::config(
+                "the oscillator needs a sampling rate above 0",
+            ));
         }
+
+        Ok(Oscillator {
+            frequency: self.frequency.ok_or_else(|| missing("frequency"))?,
+            sampling_rate: sampling_rate,
+            index: self.index.ok_or_else(|| missing("index"))?,
+        })
     }
 }
 
@@ -0,0 +1,146 @@
+// what happens to waves at one edge of the grid
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Edge {
+    // pressure release, cells outside the grid are zero and waves come back inverted
+    Dirichlet,
+    // rigid wall, cells outside the grid mirror the ones inside
+    Neumann,
+    // waves leaving the grid come in at the opposite edge, which has to be periodic as well
+    Periodic,
+    // first and second order mur absorbing boundary on the outermost cells
+    Mur1,
+    Mur2,
+    // perfectly matched layer over the outermost `thickness` cells
+    Pml { thickness: usize },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Boundary {
+    pub left: Edge,
+    pub right: Edge,
+    pub top: Edge,
+    pub bottom: Edge,
+}
+
+impl Default for Boundary {
+    fn default() -> Boundary {
+        Boundary::all(Edge::Dirichlet)
+    }
+}
+
+impl Boundary {
+    pub fn all(edge: Edge) -> Boundary {
+        Boundary {
+            left: edge,
+            right: edge,
+            top: edge,
+            bottom: edge,
+        }
+    }
+
+    // mur or pml on any edge
+    pub fn is_absorbing(&self) -> bool {
+        [self.left, self.right, self.top, self.bottom]
+            .iter()
+            .any(|edge| edge.is_mur() || edge.pml_thickness() > 0)
+    }
+
+    pub fn has_pml(&self) -> bool {
+        [self.left, self.right, self.top, self.bottom]
+            .iter()
+            .any(|edge| edge.pml_thickness() > 0)
+    }
+
+    // layers on opposite edges may not overlap, mur needs a cell next to the edge
+    // and periodic edges come in pairs
+    pub fn fits(&self, width: usize, height: usize) -> bool {
+        let fits = |low: Edge, high: Edge, len: usize| {
+            low.pml_thickness() + high.pml_thickness() <= len
+                && (len >= 2 || !(low.is_mur() || high.is_mur()))
+                && (low == Edge::Periodic) == (high == Edge::Periodic)
+        };
+        fits(self.left, self.right, width) && fits(self.top, self.bottom, height)
+    }
+}
+
+// edges of a volume, front is z = 0 and back z = depth - 1.
+// mur and pml are left to the 2d simulator.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Boundary3D {
+    pub left: Edge,
+    pub right: Edge,
+    pub top: Edge,
+    pub bottom: Edge,
+    pub front: Edge,
+    pub back: Edge,
+}
+
+impl Default for Boundary3D {
+    fn default() -> Boundary3D {
+        Boundary3D::all(Edge::Dirichlet)
+    }
+}
+
+impl Boundary3D {
+    pub fn all(edge: Edge) -> Boundary3D {
+        Boundary3D {
+            left: edge,
+            right: edge,
+            top: edge,
+            bottom: edge,
+            front: edge,
+            back: edge,
+        }
+    }
+
+    // no absorbing edges and periodic edges come in pairs
+    pub fn is_valid(&self) -> bool {
+        let pair = |low: Edge, high: Edge| {
+            !(low.is_mur() || high.is_mur())
+                && low.pml_thickness() + high.pml_thickness() == 0
+                && (low == Edge::Periodic) == (high == Edge::Periodic)
+        };
+        pair(self.left, self.right) && pair(self.top, self.bottom) && pair(self.front, self.back)
+    }
+}
+
+// ends of a string or tube
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum End {
+    // held still, a string clamped at a bridge or the open end of a pipe, whose pressure is
+    // the room's
+    Fixed,
+    // zero slope, a string end free to slide or the closed end of a pipe
+    Free,
+    // waves leave as if the line went on, like a matched transmission line
+    Open,
+}
+
+impl Default for End {
+    fn default() -> End {
+        End::Fixed
+    }
+}
+
+impl End {
+    pub fn edge(&self) -> Edge {
+        match self {
+            End::Fixed => Edge::Dirichlet,
+            End::Free => Edge::Neumann,
+            End::Open => Edge::Mur1,
+        }
+    }
+}
+
+impl Edge {
+    pub fn is_mur(&self) -> bool {
+        matches!(self, Edge::Mur1 | Edge::Mur2)
+    }
+
+    pub fn pml_thickness(&self) -> usize {
+        match self {
+            Edge::Pml { thickness } => *thickness,
+            _ => 0,
+        }
+    }
+}
@@ -0,0 +1,257 @@
+use super::{
+    Backend, Boundary, Error, Formulation, Physics, Scalar, Settings, Stencil, Tiling, Watch,
+    WaveSimulator,
+};
+
+// everything but the size defaults to Settings::new. the spec and field are kept in the
+// precision's scalar, set the precision first to hand over all of an f64.
+#[derive(Clone, Debug)]
+pub struct WaveSimulatorBuilder<T = f32> {
+    width: Option<usize>,
+    height: Option<usize>,
+    num_threads: Option<usize>,
+    dx: Option<f32>,
+    dt: Option<f32>,
+    sample_rate: Option<u32>,
+    boundary: Option<Boundary>,
+    stencil: Option<Stencil>,
+    variable_density: Option<bool>,
+    formulation: Option<Formulation>,
+    backend: Option<Backend>,
+    simd: Option<bool>,
+    tiling: Option<Option<Tiling>>,
+    watch: Option<Option<Watch>>,
+    // propagation ratio and dumping ratio of every cell, row by row
+    space_spec: Option<Vec<(T, T)>>,
+    // the field starts at rest
+    field: Option<Vec<T>>,
+}
+
+impl WaveSimulator {
+    pub fn builder() -> WaveSimulatorBuilder {
+        WaveSimulatorBuilder {
+            width: None,
+            height: None,
+            num_threads: None,
+            dx: None,
+            dt: None,
+            sample_rate: None,
+            boundary: None,
+            stencil: None,
+            variable_density: None,
+            formulation: None,
+            backend: None,
+            simd: None,
+            tiling: None,
+            watch: None,
+            space_spec: None,
+            field: None,
+        }
+    }
+}
+
+impl<T: Scalar> WaveSimulatorBuilder<T> {
+    pub fn size(&self, width: usize, height: usize) -> Self {
+        Self {
+            width: Some(width),
+            height: Some(height),
+            ..self.clone()
+        }
+    }
+
+    pub fn threads(&self, n: usize) -> Self {
+        Self {
+            num_threads: Some(n),
+            ..self.clone()
+        }
+    }
+
+    pub fn dx(&self, dx: f32) -> Self {
+        Self {
+            dx: Some(dx),
+            ..self.clone()
+        }
+    }
+
+    pub fn dt(&self, dt: f32) -> Self {
+        Self {
+            dt: Some(dt),
+            ..self.clone()
+        }
+    }
+
+    // rate the mics are recorded at, in hertz
+    pub fn sample_rate(&self, sample_rate: u32) -> Self {
+        Self {
+            sample_rate: Some(sample_rate),
+            ..self.clone()
+        }
+    }
+
+    pub fn boundary(&self, boundary: Boundary) -> Self {
+        Self {
+            boundary: Some(boundary),
+            ..self.clone()
+        }
+    }
+
+    pub fn stencil(&self, stencil: Stencil) -> Self {
+        Self {
+            stencil: Some(stencil),
+            ..self.clone()
+        }
+    }
+
+    pub fn variable_density(&self, variable_density: bool) -> Self {
+        Self {
+            variable_density: Some(variable_density),
+            ..self.clone()
+        }
+    }
+
+    // a plate reads the propagation ratios of the spec as stiffnesses
+    pub fn formulation(&self, formulation: Formulation) -> Self {
+        Self {
+            formulation: Some(formulation),
+            ..self.clone()
+        }
+    }
+
+    pub fn backend(&self, backend: Backend) -> Self {
+        Self {
+            backend: Some(backend),
+            ..self.clone()
+        }
+    }
+
+    pub fn simd(&self, simd: bool) -> Self {
+        Self {
+            simd: Some(simd),
+            ..self.clone()
+        }
+    }
+
+    pub fn tiling(&self, tiling: Option<Tiling>) -> Self {
+        Self {
+            tiling: Some(tiling),
+            ..self.clone()
+        }
+    }
+
+    pub fn watch(&self, watch: Option<Watch>) -> Self {
+        Self {
+            watch: Some(watch),
+            ..self.clone()
+        }
+    }
+
+    // the scalar the fields are kept in, f32 or f64. a spec and field set before are
+    // converted.
+    pub fn precision<U: Scalar>(&self) -> WaveSimulatorBuilder<U> {
+        let convert = |value: T| U::from_f64(value.to_f64());
+        WaveSimulatorBuilder {
+            width: self.width,
+            height: self.height,
+            num_threads: self.num_threads,
+            dx: self.dx,
+            dt: self.dt,
+            sample_rate: self.sample_rate,
+            boundary: self.boundary,
+            stencil: self.stencil,
+            variable_density: self.variable_density,
+            formulation: self.formulation,
+            backend: self.backend,
+            simd: self.simd,
+            tiling: self.tiling,
+            watch: self.watch,
+            space_spec: self.space_spec.as_ref().map(|space_spec| {
+                space_spec
+                    .iter()
+                    .map(|&(c, k)| (convert(c), convert(k)))
+                    .collect()
+            }),
+            field: self
+                .field
+                .as_ref()
+                .map(|field| field.iter().map(|&value| convert(value)).collect()),
+        }
+    }
+
+    pub fn space_spec(&self, space_spec: Vec<(T, T)>) -> Self {
+        Self {
+            space_spec: Some(space_spec),
+            ..self.clone()
+        }
+    }
+
+    pub fn field(&self, field: Vec<T>) -> Self {
+        Self {
+            field: Some(field),
+            ..self.clone()
+        }
+    }
+
+    // what build() hands to WaveSimulator::with_settings, fails without a size
+    pub fn settings(&self) -> Result<Settings, Error> {
+        let (width, height) = match (self.width, self.height) {
+            (Some(width), Some(height)) => (width, height),
+            _ => return Err(Error::config("the simulator has no size")),
+        };
+        let defaults = Settings::new(width, height);
+
+        Ok(Settings {
+            num_threads: self.num_threads.unwrap_or(defaults.num_threads),
+            physics: Physics {
+                dx: self.dx.unwrap_or(defaults.physics.dx),
+                dt: self.dt.unwrap_or(defaults.physics.dt),
+                sample_rate: self.sample_rate.unwrap_or(defaults.physics.sample_rate),
+            },
+            boundary: self.boundary.unwrap_or(defaults.boundary),
+            stencil: self.stencil.unwrap_or(defaults.stencil),
+            variable_density: self.variable_density.unwrap_or(defaults.variable_density),
+            formulation: self.formulation.unwrap_or(defaults.formulation),
+            backend: self.backend.unwrap_or(defaults.backend),
+            simd: self.simd.unwrap_or(defaults.simd),
+            tiling: self.tiling.unwrap_or(defaults.tiling),
+            watch: self.watch.unwrap_or(defaults.watch),
+            ..defaults
+        })
+    }
+
+    // checks whatever with_settings checks, and that the spec and field fit the grid. the spec
+    // is held to the same limit as any other.
+    pub fn build(&self) -> Result<WaveSimulator<T>, Error> {
+        let settings = self.settings()?;
+        let cells = settings.width * settings.height;
+        let wrong_len = |what: &str, len: usize| {
+            Error::Config(format!(
+                "the {} has {} cells, the grid {}",
+                what, len, cells
+            ))
+        };
+
+        if let Some(space_spec) = &self.space_spec {
+            if space_spec.len() != cells {
+                return Err(wrong_len("spec", space_spec.len()));
+            }
+        }
+        if let Some(field) = &self.field {
+            if field.len() != cells {
+                return Err(wrong_len("field", field.len()));
+            }
+            if field.iter().any(|value| !value.to_f64().is_finite()) {
+                return Err(Error::config("the field is not finite"));
+            }
+        }
+
+        // the spec replaces the default before it is checked, air needn't fit the physics
+        let space_spec = self.space_spec.as_ref().map(|space_spec| &space_spec[..]);
+        let mut wave_simulator = WaveSimulator::<T>::with_space_spec(settings, space_spec)?;
+
+        if let Some(field) = &self.field {
+            wave_simulator.grid_mut().set_field(field);
+        }
+
+        Ok(wave_simulator)
+    }
+}
@@ -0,0 +1,266 @@
+use std::any::Any;
+
+use super::{Error, Physics, Scalar, WaveSimulator, WaveSimulator1D, WaveSimulator3D};
+
+// a simulator an Instrument can step and push on. cells are numbered like the fields,
+// x + y * width + z * width * height.
+pub trait Domain: Any {
+    // width, height and depth in cells, a string is one high and one deep
+    fn size(&self) -> [usize; 3];
+
+    fn physics(&self) -> Physics;
+
+    fn step(&mut self) -> Result<(), Error>;
+
+    fn value(&self, cell: usize) -> f64;
+
+    // moves a cell by `by` on top of what the last step left, like a force acting over it.
+    // the watch isn't reset by it, a blow up the push feeds still counts as growth.
+    fn push(&mut self, cell: usize, by: f64);
+
+    // zeroes the field right away, the spec is kept. a blow up is forgotten, a worker panic
+    // isn't.
+    fn reset(&mut self) -> Result<(), Error>;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+// where a coupling touches a domain
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Contact {
+    Point([usize; 3]),
+    // the cells on the straight way from the first to the second, both included
+    Line([usize; 3], [usize; 3]),
+}
+
+// a spring from a contact on one domain to a contact on another, each given as the id
+// Instrument::add returned. every step a cell of `to` is pulled toward its cell of `from`
+// by stiffness times their difference, and the `from` cell is pulled back by `reaction`
+// times that: 0 drives one way, 1 is as if the two cells weighed the same.
+// the longer contact decides how many cells are paired, the shorter one is stretched along it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Coupling {
+    pub from: (usize, Contact),
+    pub to: (usize, Contact),
+    // acceleration per unit of difference, in 1/s^2
+    pub stiffness: f32,
+    pub reaction: f32,
+}
+
+// domains sharing a dt, stepped together and pushing on each other through couplings.
+// e.g. a string into a bridge, the bridge into a plate, the plate into the air.
+#[derive(Default)]
+pub struct Instrument {
+    domains: Vec<Box<dyn Domain>>,
+    // each with the pairs of cells it pulls together
+    couplings: Vec<(Coupling, Vec<(usize, usize)>)>,
+    // domain, cell and how far, gathered before the step and pushed after it
+    pushes: Vec<(usize, usize, f64)>,
+    step_count: usize,
+    // the domain whose step failed, see Error::OutOfStep
+    failed: Option<usize>,
+}
+
+impl Contact {
+    fn cells(&self, size: [usize; 3]) -> Result<Vec<usize>, Error> {
+        let (start, end) = match *self {
+            Contact::Point(at) => (at, at),
+            Contact::Line(start, end) => (start, end),
+        };
+        for axis in 0..3 {
+            if start[axis] >= size[axis] || end[axis] >= size[axis] {
+                return Err(Error::config("a contact is outside its domain"));
+            }
+        }
+
+        let steps = (0..3)
+            .map(|axis| {
+                if end[axis] >= start[axis] {
+                    end[axis] - start[axis]
+                } else {
+                    start[axis] - end[axis]
+                }
+            })
+            .max()
+            .unwrap_or(0);
+        let along = |axis: usize, i: usize| {
+            let (start, end) = (start[axis] as f32, end[axis] as f32);
+            let t = if steps == 0 {
+                0.0
+            } else {
+                i as f32 / steps as f32
+            };
+            (start + (end - start) * t).round() as usize
+        };
+
+        Ok((0..=steps)
+            .map(|i| along(0, i) + (along(1, i) + along(2, i) * size[1]) * size[0])
+            .collect())
+    }
+}
+
+impl Instrument {
+    pub fn new() -> Instrument {
+        Instrument::default()
+    }
+
+    // the id couplings and domain() know it by. every domain steps by the first one's dt and
+    // is recorded at its sample rate.
+    pub fn add<D: Domain>(&mut self, domain: D) -> Result<usize, Error> {
+        if let Some(first) = self.domains.first() {
+            let (first, other) = (first.physics(), domain.physics());
+            if (first.dt - other.dt).abs() > first.dt * 1e-6 {
+                return Err(Error::config("the domains don't share a dt"));
+            }
+            if first.sample_rate != other.sample_rate {
+                return Err(Error::config("the domains don't share a sample rate"));
+            }
+        }
+        self.domains.push(Box::new(domain));
+        Ok(self.domains.len() - 1)
+    }
+
+    // the spring alone is kept well inside the 4 / dt^2 it could take, the rest of the
+    // room is left to the waves
+    pub fn couple(&mut self, coupling: Coupling) -> Result<(), Error> {
+        let (from, to) = (coupling.from.0, coupling.to.0);
+        if from >= self.domains.len() || to >= self.domains.len() {
+            return Err(Error::config("a coupling names a domain that wasn't added"));
+        }
+        if !(coupling.stiffness >= 0.0 && coupling.reaction >= 0.0) {
+            return Err(Error::config("a coupling can't pull apart"));
+        }
+        let dt = self.domains[from].physics().dt;
+        if coupling.stiffness * (1.0 + coupling.reaction) * dt * dt > 1.0 {
+            return Err(Error::config("a coupling is too stiff for dt"));
+        }
+
+        let from_cells = coupling.from.1.cells(self.domains[from].size())?;
+        let to_cells = coupling.to.1.cells(self.domains[to].size())?;
+        let len = from_cells.len().max(to_cells.len());
+        let pick = |cells: &[usize], i: usize| cells[i * cells.len() / len];
+        let pairs = (0..len)
+            .map(|i| (pick(&from_cells, i), pick(&to_cells, i)))
+            .collect();
+
+        self.couplings.push((coupling, pairs));
+        Ok(())
+    }
+
+    pub fn domain<D: Domain>(&self, id: usize) -> Option<&D> {
+        self.domains.get(id)?.as_any().downcast_ref()
+    }
+
+    pub fn domain_mut<D: Domain>(&mut self, id: usize) -> Option<&mut D> {
+        self.domains.get_mut(id)?.as_any_mut().downcast_mut()
+    }
+
+    pub fn current_step(&self) -> usize {
+        self.step_count
+    }
+
+    // once a domain failed to step every call fails with Error::OutOfStep, until a reset
+    pub fn step(&mut self) -> Result<(), Error> {
+        if let Some(domain) = self.failed {
+            return Err(Error::OutOfStep(domain));
+        }
+        let dt = match self.domains.first() {
+            Some(domain) => domain.physics().dt as f64,
+            None => return Ok(()),
+        };
+
+        // every spring is read before anything moves
+        self.pushes.clear();
+        for (coupling, pairs) in self.couplings.iter() {
+            let (from, to) = (coupling.from.0, coupling.to.0);
+            let stiffness = coupling.stiffness as f64 * dt * dt;
+            for &(a, b) in pairs.iter() {
+                let pull = stiffness * (self.domains[from].value(a) - self.domains[to].value(b));
+                self.pushes.push((to, b, pull));
+                self.pushes
+                    .push((from, a, -pull * coupling.reaction as f64));
+            }
+        }
+
+        for (id, domain) in self.domains.iter_mut().enumerate() {
+            if let Err(error) = domain.step() {
+                self.failed = Some(id);
+                return Err(error);
+            }
+        }
+        for &(domain, cell, by) in self.pushes.iter() {
+            self.domains[domain].push(cell, by);
+        }
+        self.step_count += 1;
+        Ok(())
+    }
+
+    pub fn run(&mut self, steps: usize) -> Result<(), Error> {
+        for _ in 0..steps {
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    // zeroes every domain, so they start out together again after one failed to step
+    pub fn reset(&mut self) -> Result<(), Error> {
+        for domain in self.domains.iter_mut() {
+            domain.reset()?;
+        }
+        self.failed = None;
+        Ok(())
+    }
+}
+
+macro_rules! domain {
+    ($simulator:ident, |$this:ident| $size:expr) => {
+        impl<T: Scalar> Domain for $simulator<T> {
+            fn size(&self) -> [usize; 3] {
+                let $this = self;
+                $size
+            }
+
+            fn physics(&self) -> Physics {
+                $simulator::physics(self)
+            }
+
+            fn step(&mut self) -> Result<(), Error> {
+                $simulator::step(self)
+            }
+
+            fn value(&self, cell: usize) -> f64 {
+                self.field()[cell].to_f64()
+            }
+
+            fn push(&mut self, cell: usize, by: f64) {
+                self.nudge(cell, T::from_f64(by));
+            }
+
+            fn reset(&mut self) -> Result<(), Error> {
+                $simulator::reset(self)
+            }
+
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+
+            fn as_any_mut(&mut self) -> &mut dyn Any {
+                self
+            }
+        }
+    };
+}
+
+domain!(WaveSimulator1D, |string| [string.length, 1, 1]);
+domain!(WaveSimulator, |simulator| [
+    simulator.width,
+    simulator.height,
+    1
+]);
+domain!(WaveSimulator3D, |simulator| [
+    simulator.width,
+    simulator.height,
+    simulator.depth
+]);
@@ -0,0 +1,52 @@
+use super::{Physics, Stencil};
+
+// how the wave equation is put on the grid
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Formulation {
+    // pressure only, second order in time
+    SecondOrder,
+    // pressure on the cells and particle velocity on the faces between them, updated in turn.
+    // always uses the density of the cells.
+    Staggered,
+    // displacement of a thin plate, u_tt = -kappa^2 lap lap u - k u_t + 2 loss lap u_t.
+    // the propagation ratio of each cell is its stiffness kappa in m^2/s, `loss` in m^2/s damps
+    // high frequencies more. dirichlet edges are simply supported, neumann edges slide.
+    Plate { loss: f32 },
+}
+
+impl Default for Formulation {
+    fn default() -> Formulation {
+        Formulation::SecondOrder
+    }
+}
+
+impl Formulation {
+    // parts of a step, every band has to finish one before any starts the next
+    pub fn phases(&self) -> usize {
+        match self {
+            Formulation::SecondOrder | Formulation::Plate { .. } => 1,
+            Formulation::Staggered => 2,
+        }
+    }
+
+    // how far the update of a cell reads, the plate takes the laplacian of the laplacian
+    pub fn radius(&self, stencil: Stencil) -> usize {
+        match self {
+            Formulation::Plate { .. } => 2,
+            _ => stencil.radius(),
+        }
+    }
+
+    // fastest propagation ratio, or stiffest plate, the physics and stencil allow
+    pub fn max_stable_speed(&self, physics: &Physics, stencil: Stencil) -> f32 {
+        match self {
+            Formulation::Plate { loss } => physics.max_stable_stiffness(*loss),
+            _ => physics.max_stable_speed(stencil),
+        }
+    }
+
+    // kappa of a plate `thickness` m thick, sqrt(D / (rho h)) with D = E h^3 / (12 (1 - nu^2))
+    pub fn plate_stiffness(young: f32, poisson: f32, density: f32, thickness: f32) -> f32 {
+        (young * thickness * thickness / (12.0 * density * (1.0 - poisson * poisson))).sqrt()
+    }
+}
@@ -0,0 +1,605 @@
+use std::ops::Range;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use super::kernel::{Band, Fields, Kernel, Window};
+use super::shared::SharedBuffer;
+use super::tiling::Update;
+use super::watch::{Monitor, Probe, Stats, Watch};
+use super::{Boundary, Edge, Error, Formulation, Order, Physics, Scalar, Settings, Space, Stencil};
+
+// the fields and spec every solver works on, and everything that touches them between steps.
+// solvers only hand the buffers to their workers inside Solver::run, which borrows the
+// solver mutably, so nothing here races with a step.
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    physics: Physics,
+    boundary: Boundary,
+    stencil: Stencil,
+    formulation: Formulation,
+    kernel: Kernel,
+    buffers: Arc<Buffers<T>>,
+    // number of steps taken so far
+    step_count: usize,
+    // space_spec changed since the coefficients were computed
+    stale: bool,
+    // none when the settings don't watch the field
+    monitor: Option<Monitor>,
+}
+
+// previous, current and next fields are rotated instead of copied.
+// the field of step n is fields[n % 3], the previous one sits right before it.
+pub(crate) struct Buffers<T> {
+    fields: [SharedBuffer<T>; 3],
+    space_spec: SharedBuffer<(T, T)>,
+    density: SharedBuffer<T>,
+    coefficient: SharedBuffer<T>,
+    damping: SharedBuffer<T>,
+    // pml auxiliary fields x and y, read from psi[n % 2] and written to the other pair
+    psi: [[SharedBuffer<T>; 2]; 2],
+    // staggered x and y velocity, updated in place
+    velocity: [SharedBuffer<T>; 2],
+    // what the updates of a run wrote, collected once it is done
+    probes: Mutex<Vec<Probe>>,
+}
+
+impl<T: Scalar> Grid<T> {
+    // starts from `space_spec` if given, row by row, the caller checked its length
+    pub(crate) fn new(
+        settings: &Settings,
+        space_spec: Option<&[(T, T)]>,
+    ) -> Result<Grid<T>, Error> {
+        let Settings {
+            width,
+            height,
+            physics,
+            boundary,
+            stencil,
+            variable_density,
+            formulation,
+            ..
+        } = *settings;
+
+        let staggered = formulation == Formulation::Staggered;
+        let plate = matches!(formulation, Formulation::Plate { .. });
+        let checks = [
+            (width == 0 || height == 0, "the grid has no cells"),
+            (
+                !physics.is_valid(),
+                "dx, dt and the sample rate have to be positive",
+            ),
+            (
+                !boundary.fits(width, height),
+                "the edges don't fit the grid or only one side of an axis is periodic",
+            ),
+            (
+                settings.tiling.map_or(false, |tiling| !tiling.is_valid()),
+                "tiles need rows and steps",
+            ),
+            (
+                settings.watch.map_or(false, |watch| !watch.is_valid()),
+                "the watch needs a window and a growth over 1",
+            ),
+            (
+                variable_density && stencil != Stencil::FivePoint,
+                "variable density needs the 5 point stencil",
+            ),
+            (
+                staggered && stencil != Stencil::FivePoint,
+                "the staggered formulation needs the 5 point stencil",
+            ),
+            (
+                staggered && boundary.is_absorbing(),
+                "the staggered formulation takes no mur or pml edges",
+            ),
+            (
+                plate && stencil != Stencil::FivePoint,
+                "the plate formulation needs the 5 point stencil",
+            ),
+            (
+                plate && (variable_density || boundary.is_absorbing()),
+                "the plate formulation takes no density, mur or pml edges",
+            ),
+        ];
+        if let Some(&(_, reason)) = checks.iter().find(|&&(failed, _)| failed) {
+            return Err(Error::config(reason));
+        }
+
+        let mut space = Space::<T>::new(width, height);
+        // a plate reads the spec as stiffness, it starts as 1 mm of steel rather than air
+        if let Some(space_spec) = space_spec {
+            space.space_spec.copy_from_slice(space_spec);
+        } else if plate {
+            let stiffness = Formulation::plate_stiffness(2e11, 0.3, 7800.0, 0.001);
+            for cell in space.space_spec.iter_mut() {
+                *cell = (T::from_f32(stiffness), T::default());
+            }
+        }
+
+        // the initial spec has to be stable
+        let max_speed = max_stable_speed(formulation, &physics, stencil, boundary, height);
+        check_spec(&space.space_spec, width, max_speed, formulation, &physics)?;
+
+        let kernel = Kernel::new(settings);
+
+        // only allocated when some edge has a layer
+        let psi_len = if kernel.has_pml() { width * height } else { 0 };
+        let psi = || SharedBuffer::new(vec![T::default(); psi_len]);
+
+        let len = width * height;
+        let buffers = Arc::new(Buffers {
+            fields: [
+                SharedBuffer::new(space.space.clone()),
+                SharedBuffer::new(space.space.clone()),
+                SharedBuffer::new(space.space),
+            ],
+            space_spec: SharedBuffer::new(space.space_spec),
+            density: SharedBuffer::new(space.space_density),
+            coefficient: SharedBuffer::new(vec![T::default(); len]),
+            damping: SharedBuffer::new(vec![T::default(); len]),
+            psi: [[psi(), psi()], [psi(), psi()]],
+            velocity: [
+                SharedBuffer::new(vec![T::default(); kernel.velocity_len().0]),
+                SharedBuffer::new(vec![T::default(); kernel.velocity_len().1]),
+            ],
+            probes: Mutex::new(Vec::new()),
+        });
+
+        let mut grid = Grid {
+            width: width,
+            height: height,
+            physics: physics,
+            boundary: boundary,
+            stencil: stencil,
+            formulation: formulation,
+            kernel: kernel,
+            buffers: buffers,
+            step_count: 0,
+            stale: true,
+            monitor: settings.watch.map(|watch| Monitor::new(watch, width)),
+        };
+        grid.prepare();
+        Ok(grid)
+    }
+
+    pub(crate) fn width(&self) -> usize {
+        self.width
+    }
+
+    pub(crate) fn height(&self) -> usize {
+        self.height
+    }
+
+    pub(crate) fn physics(&self) -> Physics {
+        self.physics
+    }
+
+    pub(crate) fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    pub(crate) fn stencil(&self) -> Stencil {
+        self.stencil
+    }
+
+    pub(crate) fn formulation(&self) -> Formulation {
+        self.formulation
+    }
+
+    pub(crate) fn kernel(&self) -> &Kernel {
+        &self.kernel
+    }
+
+    pub(crate) fn buffers(&self) -> &Arc<Buffers<T>> {
+        &self.buffers
+    }
+
+    pub(crate) fn step_count(&self) -> usize {
+        self.step_count
+    }
+
+    // called by the solvers before they hand the buffers to the workers
+    pub(crate) fn prepare(&mut self) {
+        if !self.stale {
+            return;
+        }
+        self.stale = false;
+
+        let len = self.width * self.height;
+        let (space_spec, coefficient, damping) = unsafe {
+            (
+                self.buffers.space_spec.slice(),
+                self.buffers.coefficient.slice_mut(0..len),
+                self.buffers.damping.slice_mut(0..len),
+            )
+        };
+        for (i, &spec) in space_spec.iter().enumerate() {
+            let (c, k) = self.kernel.coefficients(spec);
+            coefficient[i] = c;
+            damping[i] = k;
+        }
+    }
+
+    // called by the solvers once the workers are done, checks the steps they took
+    pub(crate) fn advanced(&mut self, steps: usize) -> Result<(), Error> {
+        // every step the current field becomes previous, next becomes current
+        // and the old previous is reused as next
+        self.step_count += steps;
+
+        let probes = std::mem::take(&mut *self.buffers.probes.lock()?);
+        match &mut self.monitor {
+            Some(monitor) => Ok(monitor.record(probes)?),
+            None => Ok(()),
+        }
+    }
+
+    // fails once the field blew up, until it is reset
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        match self.monitor.as_ref().and_then(Monitor::failed) {
+            Some(instability) => Err(instability.into()),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn watch(&self) -> Option<Watch> {
+        self.monitor.as_ref().map(Monitor::watch)
+    }
+
+    // energy and peak after the last step
+    pub(crate) fn stats(&self) -> Option<Stats> {
+        self.monitor.as_ref()?.stats()
+    }
+
+    // energy was added to the field, it may grow without blowing up
+    fn added(&mut self) {
+        let step = self.step_count;
+        if let Some(monitor) = &mut self.monitor {
+            monitor.reset(step);
+        }
+    }
+
+    // the caller makes sure the updates running at the same time are from one pass
+    pub(crate) unsafe fn update(&self, update: &Update) {
+        self.buffers.update(&self.kernel, update)
+    }
+
+    pub(crate) fn max_stable_speed(&self) -> f32 {
+        max_stable_speed(
+            self.formulation,
+            &self.physics,
+            self.stencil,
+            self.boundary,
+            self.height,
+        )
+    }
+
+    pub(crate) fn add_gauss(&mut self, x: f32, y: f32, sigma: f32, power: f32) {
+        let mut gauss = Space::<T>::new(self.width, self.height);
+        gauss.add_gauss(x, y, sigma, power);
+        self.added();
+
+        // previous and current
+        for k in 0..2 {
+            let field = unsafe { self.field_buffer(k).slice_mut(0..gauss.space.len()) };
+            for (value, g) in field.iter_mut().zip(gauss.space.iter()) {
+                *value += *g;
+            }
+        }
+    }
+
+    // previous too, so the field starts at rest
+    pub(crate) fn set_field(&mut self, field: &[T]) {
+        self.added();
+
+        for k in 0..2 {
+            let buffer = unsafe { self.field_buffer(k).slice_mut(0..field.len()) };
+            buffer.copy_from_slice(field);
+        }
+    }
+
+    // pausing is up to whoever drives the solver, Pause and Resume do nothing here.
+    // a propagation ratio over the cfl limit is refused, the spec is left as it was.
+    pub(crate) fn apply(&mut self, order: Order) -> Result<(), Error> {
+        use super::Impulse::*;
+        use super::Parameter::*;
+        use Order::*;
+
+        let (width, height) = (self.width, self.height);
+        let index = |x: usize, y: usize| {
+            if x < width && y < height {
+                Some(x + y * width)
+            } else {
+                None
+            }
+        };
+
+        match order {
+            Change(PropagationRatio(x, y, value)) => {
+                if let Some(i) = index(x, y) {
+                    self.check_stable(value)?;
+                    self.space_spec_mut()[i].0 = T::from_f32(value);
+                }
+            }
+            Change(DumpingRatio(x, y, value)) => {
+                if let Some(i) = index(x, y) {
+                    self.space_spec_mut()[i].1 = T::from_f32(value);
+                }
+            }
+            Change(Density(x, y, value)) => {
+                if let Some(i) = index(x, y) {
+                    self.density_mut()[i] = T::from_f32(value);
+                }
+            }
+            FillMaterial(region, material) => {
+                let spec = material.spec();
+                self.apply(Fill(region.clone(), spec))?;
+
+                let density = self.density_mut();
+                for y in 0..height {
+                    for x in 0..width {
+                        if region.contains(x, y, width) {
+                            density[x + y * width] = T::from_f32(material.density);
+                        }
+                    }
+                }
+            }
+            Fill(region, spec) => {
+                self.check_stable(spec.0)?;
+                let spec = (T::from_f32(spec.0), T::from_f32(spec.1));
+                let space_spec = self.space_spec_mut();
+                for y in 0..height {
+                    for x in 0..width {
+                        if region.contains(x, y, width) {
+                            space_spec[x + y * width] = spec;
+                        }
+                    }
+                }
+            }
+            Reset => {
+                let step = self.step_count;
+                if let Some(monitor) = &mut self.monitor {
+                    monitor.clear(step);
+                }
+
+                // next is overwritten by the next step anyway
+                for k in 0..2 {
+                    let field = unsafe { self.field_buffer(k).slice_mut(0..width * height) };
+                    for value in field.iter_mut() {
+                        *value = T::default();
+                    }
+                }
+                let psi = self.buffers.psi.iter().flat_map(|pair| pair.iter());
+                for buffer in psi.chain(self.buffers.velocity.iter()) {
+                    for value in unsafe { buffer.slice_mut(0..buffer.len()) } {
+                        *value = T::default();
+                    }
+                }
+            }
+            Impulse(Gauss { x, y, sigma, power }) => self.add_gauss(x, y, sigma, power),
+            Impulse(Point { x, y, power }) => {
+                self.added();
+                let (left, top) = (x.floor(), y.floor());
+                let (fx, fy) = (x - left, y - top);
+                let corners = [
+                    (0, 0, (1.0 - fx) * (1.0 - fy)),
+                    (1, 0, fx * (1.0 - fy)),
+                    (0, 1, (1.0 - fx) * fy),
+                    (1, 1, fx * fy),
+                ];
+
+                for &(ox, oy, weight) in corners.iter() {
+                    let (cx, cy) = (left + ox as f32, top + oy as f32);
+                    if cx < 0.0 || cy < 0.0 {
+                        continue;
+                    }
+                    if let Some(i) = index(cx as usize, cy as usize) {
+                        // previous and current, like add_gauss
+                        for k in 0..2 {
+                            unsafe {
+                                self.field_buffer(k).slice_mut(i..i + 1)[0] +=
+                                    T::from_f32(power * weight)
+                            };
+                        }
+                    }
+                }
+            }
+            Pause | Resume => {}
+        }
+        Ok(())
+    }
+
+    fn check_stable(&self, speed: f32) -> Result<(), Error> {
+        if speed.abs() > self.max_stable_speed() {
+            return Err(Error::Config(format!(
+                "propagation ratio {} is above {}, the cfl limit for dx {}, dt {} and {:?}",
+                speed,
+                self.max_stable_speed(),
+                self.physics.dx,
+                self.physics.dt,
+                self.stencil
+            )));
+        }
+        Ok(())
+    }
+
+    // 0: previous, 1: current, 2: next
+    fn field_buffer(&self, k: usize) -> &SharedBuffer<T> {
+        &self.buffers.fields[(self.step_count + k + 2) % 3]
+    }
+
+    pub(crate) fn field(&self) -> &[T] {
+        unsafe { self.field_buffer(1).slice() }
+    }
+
+    pub(crate) fn field_mut(&mut self) -> &mut [T] {
+        self.added();
+        let len = self.width * self.height;
+        unsafe { self.field_buffer(1).slice_mut(0..len) }
+    }
+
+    // a force acting every step. unlike field_mut the watch keeps judging the growth, so a
+    // blow up it feeds is still caught early.
+    pub(crate) fn nudge(&mut self, i: usize, by: T) {
+        unsafe { self.field_buffer(1).slice_mut(i..i + 1)[0] += by };
+    }
+
+    pub(crate) fn space_spec(&self) -> &[(T, T)] {
+        unsafe { self.buffers.space_spec.slice() }
+    }
+
+    pub(crate) fn space_spec_mut(&mut self) -> &mut [(T, T)] {
+        self.stale = true;
+        let len = self.buffers.space_spec.len();
+        unsafe { self.buffers.space_spec.slice_mut(0..len) }
+    }
+
+    pub(crate) fn density(&self) -> &[T] {
+        unsafe { self.buffers.density.slice() }
+    }
+
+    pub(crate) fn density_mut(&mut self) -> &mut [T] {
+        let len = self.buffers.density.len();
+        unsafe { self.buffers.density.slice_mut(0..len) }
+    }
+
+    pub(crate) fn velocity(&self) -> Option<(&[T], &[T])> {
+        match self.formulation {
+            Formulation::SecondOrder | Formulation::Plate { .. } => None,
+            Formulation::Staggered => unsafe {
+                Some((
+                    self.buffers.velocity[0].slice(),
+                    self.buffers.velocity[1].slice(),
+                ))
+            },
+        }
+    }
+}
+
+impl<T: Scalar> Buffers<T> {
+    pub(crate) unsafe fn update(&self, kernel: &Kernel, update: &Update) {
+        let Update { step, phase, .. } = *update;
+        let (fields, mut band) = self.views(kernel, step, phase, &update.rows);
+        kernel.update(phase, update.rows.clone(), &fields, &mut band);
+
+        if let Some(probe) = band.probe {
+            let mut probes = self.probes.lock().unwrap_or_else(PoisonError::into_inner);
+            probes.push(probe);
+        }
+    }
+
+    // what an update reads and writes. staggered velocity is written in the first phase
+    // and read in the second.
+    unsafe fn views(
+        &self,
+        kernel: &Kernel,
+        step: usize,
+        phase: usize,
+        rows: &Range<usize>,
+    ) -> (Fields<T>, Band<T>) {
+        let width = kernel.width();
+        let field_rows = rows.start * width..rows.end * width;
+        let window = kernel.window(rows);
+        let window = window.start * width..window.end * width;
+        let (psi_rows, psi_window) = if kernel.has_pml() {
+            (field_rows.clone(), window.clone())
+        } else {
+            (0..0, 0..0)
+        };
+        let (psi, psi_next) = (&self.psi[step % 2], &self.psi[(step + 1) % 2]);
+
+        let (x_faces, y_faces) = if phase == 0 {
+            kernel.velocity_ranges(rows)
+        } else {
+            (0..0, 0..0)
+        };
+        let velocity = |k: usize| {
+            if phase == 0 {
+                &[][..]
+            } else {
+                self.velocity[k].slice()
+            }
+        };
+
+        (
+            Fields {
+                previous: read(&self.fields[(step + 2) % 3], window.clone()),
+                current: read(&self.fields[step % 3], window),
+                space_spec: self.space_spec.slice(),
+                density: self.density.slice(),
+                coefficient: self.coefficient.slice(),
+                damping: self.damping.slice(),
+                psi_x: read(&psi[0], psi_window.clone()),
+                psi_y: read(&psi[1], psi_window),
+                velocity_x: velocity(0),
+                velocity_y: velocity(1),
+            },
+            Band {
+                next: self.fields[(step + 1) % 3].slice_mut(field_rows),
+                psi_x: psi_next[0].slice_mut(psi_rows.clone()),
+                psi_y: psi_next[1].slice_mut(psi_rows),
+                velocity_x: self.velocity[0].slice_mut(x_faces),
+                velocity_y: self.velocity[1].slice_mut(y_faces),
+                // the field of a step is done with its last phase
+                probe: if kernel.watched() && phase + 1 == kernel.phases() {
+                    Some(Probe::new(step + 1))
+                } else {
+                    None
+                },
+            },
+        )
+    }
+}
+
+// the caller makes sure nobody writes the range meanwhile
+unsafe fn read<T: Scalar>(buffer: &SharedBuffer<T>, range: Range<usize>) -> Window<T> {
+    Window::new(buffer.slice_range(range.clone()), range.start)
+}
+
+// a single row mirrored above and below is the 3 point stencil, which holds up to
+// c dt / dx = 1 and has no dispersion there
+fn max_stable_speed(
+    formulation: Formulation,
+    physics: &Physics,
+    stencil: Stencil,
+    boundary: Boundary,
+    height: usize,
+) -> f32 {
+    let row = height == 1 && boundary.top == Edge::Neumann && boundary.bottom == Edge::Neumann;
+    if row && stencil == Stencil::FivePoint && formulation == Formulation::SecondOrder {
+        physics.dx / physics.dt
+    } else {
+        formulation.max_stable_speed(physics, stencil)
+    }
+}
+
+// the first cell whose propagation ratio, or stiffness on a plate, is over the limit fails
+fn check_spec<T: Scalar>(
+    space_spec: &[(T, T)],
+    width: usize,
+    max_speed: f32,
+    formulation: Formulation,
+    physics: &Physics,
+) -> Result<(), Error> {
+    let i = match space_spec
+        .iter()
+        .position(|&(c, _)| c.to_f32().is_nan() || c.to_f32().abs() > max_speed)
+    {
+        Some(i) => i,
+        None => return Ok(()),
+    };
+    let (what, limit) = match formulation {
+        Formulation::Plate { .. } => ("stiffness", "the plate's stiffness limit"),
+        _ => ("propagation ratio", "the cfl limit"),
+    };
+    Err(Error::Config(format!(
+        "{} {} of cell ({}, {}) is over {} of {} for dx {} and dt {}",
+        what,
+        space_spec[i].0,
+        i % width,
+        i / width,
+        limit,
+        max_speed,
+        physics.dx,
+        physics.dt
+    )))
+}
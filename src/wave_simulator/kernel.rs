@@ -0,0 +1,875 @@
+use std::ops::{Index, Range};
+
+use super::simd::{Lanes, Simd};
+use super::watch::Probe;
+use super::{Boundary, Edge, Formulation, Scalar, Settings, Stencil, Tiling};
+
+// reflection a pml layer is tuned for, at normal incidence
+const PML_REFLECTION: f32 = 1e-4;
+
+// the update rule of one step, shared by every worker
+#[derive(Clone, Debug)]
+pub(crate) struct Kernel {
+    width: usize,
+    height: usize,
+    dx: f32,
+    dt: f32,
+    boundary: Boundary,
+    stencil: Stencil,
+    variable_density: bool,
+    formulation: Formulation,
+    has_pml: bool,
+    simd: Simd,
+    // none when the run can't be tiled
+    tiling: Option<Tiling>,
+    // record the energy of every step
+    watched: bool,
+    // cells along the left, right, top and bottom edge that can't take the fast path
+    margin: [usize; 4],
+}
+
+// read only during a step
+pub(crate) struct Fields<'a, T> {
+    pub previous: Window<'a, T>,
+    pub current: Window<'a, T>,
+    pub space_spec: &'a [(T, T)],
+    pub density: &'a [T],
+    // dt^2 c^2 / dx^2 and k dt of every cell, from space_spec
+    pub coefficient: &'a [T],
+    pub damping: &'a [T],
+    // pml auxiliary fields on the right and bottom face of each cell, empty without pml
+    pub psi_x: Window<'a, T>,
+    pub psi_y: Window<'a, T>,
+    // staggered velocity on the left and top face of each cell, plus the right and bottom
+    // edge of the grid. empty while it is being updated.
+    pub velocity_x: &'a [T],
+    pub velocity_y: &'a [T],
+}
+
+// the rows of a field an update reads, indexed like the whole field
+#[derive(Copy, Clone)]
+pub(crate) struct Window<'a, T> {
+    values: &'a [T],
+    // index of the first value in the whole field
+    origin: usize,
+}
+
+// what one worker writes, every slice starts at the first row of its band
+pub(crate) struct Band<'a, T> {
+    pub next: &'a mut [T],
+    pub psi_x: &'a mut [T],
+    pub psi_y: &'a mut [T],
+    pub velocity_x: &'a mut [T],
+    pub velocity_y: &'a mut [T],
+    // energy of the rows written, when the step is watched and done with them
+    pub probe: Option<Probe>,
+}
+
+impl Kernel {
+    pub fn new(settings: &Settings) -> Kernel {
+        let Settings {
+            width,
+            height,
+            physics,
+            boundary,
+            stencil,
+            variable_density,
+            formulation,
+            simd,
+            tiling,
+            watch,
+            ..
+        } = *settings;
+
+        // the staggered formulation has two phases, and with periodic top and bottom edges
+        // the first rows of a step need the last rows of the one before
+        let tiling = tiling.filter(|tiling| {
+            tiling.steps > 1
+                && formulation == Formulation::SecondOrder
+                && boundary.top != Edge::Periodic
+        });
+
+        // the stencil of a cell closer to the edge than its radius reaches outside the grid
+        let radius = formulation.radius(stencil);
+        let margin = |edge: Edge| edge.pml_thickness().max(radius);
+
+        Kernel {
+            width: width,
+            height: height,
+            dx: physics.dx,
+            dt: physics.dt,
+            boundary: boundary,
+            stencil: stencil,
+            variable_density: variable_density,
+            formulation: formulation,
+            has_pml: boundary.has_pml(),
+            simd: if simd { Simd::detect() } else { Simd::Scalar },
+            tiling: tiling,
+            watched: watch.is_some(),
+            margin: [
+                margin(boundary.left),
+                margin(boundary.right),
+                margin(boundary.top),
+                margin(boundary.bottom),
+            ],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn tiling(&self) -> Option<Tiling> {
+        self.tiling
+    }
+
+    // rows between a cell and the farthest one its update reads.
+    // a mur cell on the top or bottom edge updates its neighbour first.
+    pub fn reach(&self) -> usize {
+        let b = &self.boundary;
+        let radius = self.formulation.radius(self.stencil);
+        if b.top.is_mur() || b.bottom.is_mur() {
+            radius + 1
+        } else {
+            radius
+        }
+    }
+
+    // rows of the fields an update of `rows` reads. tiled runs update different steps at
+    // once, so they can't read the whole field.
+    pub fn window(&self, rows: &Range<usize>) -> Range<usize> {
+        if self.tiling.is_some() {
+            rows.start.saturating_sub(self.reach())..(rows.end + self.reach()).min(self.height)
+        } else {
+            0..self.height
+        }
+    }
+
+    pub fn watched(&self) -> bool {
+        self.watched
+    }
+
+    pub fn has_pml(&self) -> bool {
+        self.has_pml
+    }
+
+    // coefficient of the laplacian and of the damping for a (speed, damping ratio) spec.
+    // a plate's is the coefficient of the laplacian of the laplacian, for a stiffness.
+    pub fn coefficients<T: Scalar>(&self, (c, k): (T, T)) -> (T, T) {
+        let (dx, dt) = (T::from_f32(self.dx), T::from_f32(self.dt));
+        match self.formulation {
+            Formulation::Plate { .. } => ((dt * dt) * (c * c) / (dx * dx * dx * dx), k * dt),
+            _ => ((dt * dt) * (c * c) / (dx * dx), k * dt),
+        }
+    }
+
+    // coefficient of the change of the laplacian, 2 loss dt / dx^2
+    fn plate_loss(&self) -> f32 {
+        match self.formulation {
+            Formulation::Plate { loss } => 2.0 * loss * self.dt / (self.dx * self.dx),
+            _ => 0.0,
+        }
+    }
+
+    pub fn phases(&self) -> usize {
+        self.formulation.phases()
+    }
+
+    // lengths of the x and y velocity fields
+    pub fn velocity_len(&self) -> (usize, usize) {
+        match self.formulation {
+            Formulation::SecondOrder | Formulation::Plate { .. } => (0, 0),
+            Formulation::Staggered => (
+                (self.width + 1) * self.height,
+                self.width * (self.height + 1),
+            ),
+        }
+    }
+
+    // x and y velocity faces owned by the band, the last band also owns the bottom edge
+    pub fn velocity_ranges(&self, rows: &Range<usize>) -> (Range<usize>, Range<usize>) {
+        match self.formulation {
+            Formulation::SecondOrder | Formulation::Plate { .. } => (0..0, 0..0),
+            Formulation::Staggered => {
+                let end = if rows.end == self.height {
+                    rows.end + 1
+                } else {
+                    rows.end
+                };
+                (
+                    rows.start * (self.width + 1)..rows.end * (self.width + 1),
+                    rows.start * self.width..end * self.width,
+                )
+            }
+        }
+    }
+
+    pub fn update<T: Scalar>(
+        &self,
+        phase: usize,
+        rows: Range<usize>,
+        fields: &Fields<T>,
+        band: &mut Band<T>,
+    ) {
+        match (self.formulation, phase) {
+            (Formulation::SecondOrder, _) | (Formulation::Plate { .. }, _) => {
+                self.update_second_order(rows, fields, band)
+            }
+            (Formulation::Staggered, 0) => self.update_velocity(rows, fields, band),
+            (Formulation::Staggered, _) => self.update_pressure(rows, fields, band),
+        }
+    }
+
+    fn update_second_order<T: Scalar>(
+        &self,
+        rows: Range<usize>,
+        fields: &Fields<T>,
+        band: &mut Band<T>,
+    ) {
+        let (width, height) = (self.width, self.height);
+        let [left, right, top, bottom] = self.margin;
+        let left = left.min(width);
+        let inner_end = width.saturating_sub(right).max(left);
+        let offset = rows.start * width;
+
+        for y in rows {
+            if y < top || y + bottom >= height {
+                for x in 0..width {
+                    self.edge_cell(x, y, fields, band, offset);
+                }
+            } else {
+                for x in 0..left {
+                    self.edge_cell(x, y, fields, band, offset);
+                }
+
+                // every cell the stencil reads is inside the grid
+                self.interior_row(left..inner_end, y, fields, band, offset);
+
+                for x in inner_end..width {
+                    self.edge_cell(x, y, fields, band, offset);
+                }
+            }
+
+            self.probe_row(y, band, offset);
+        }
+    }
+
+    fn interior_row<T: Scalar>(
+        &self,
+        xs: Range<usize>,
+        y: usize,
+        fields: &Fields<T>,
+        band: &mut Band<T>,
+        offset: usize,
+    ) {
+        let next = &mut *band.next;
+
+        // the caller keeps the stencil of every cell in xs inside the grid
+        unsafe {
+            if let Formulation::Plate { .. } = self.formulation {
+                return match self.simd {
+                    #[cfg(target_arch = "x86_64")]
+                    Simd::Avx => plate_row_avx(self, xs, y, fields, next, offset),
+                    #[cfg(target_arch = "x86_64")]
+                    Simd::Sse => plate_row::<T, T::Sse>(self, xs, y, fields, next, offset),
+                    _ => plate_row::<T, T>(self, xs, y, fields, next, offset),
+                };
+            }
+
+            match self.simd {
+                #[cfg(target_arch = "x86_64")]
+                Simd::Avx => row_avx(self, xs, y, fields, next, offset),
+                #[cfg(target_arch = "x86_64")]
+                Simd::Sse => row::<T, T::Sse>(self, xs, y, fields, next, offset),
+                _ => row::<T, T>(self, xs, y, fields, next, offset),
+            }
+        }
+    }
+
+    // v_t = -grad p / rho on the faces of the band, in place
+    fn update_velocity<T: Scalar>(
+        &self,
+        rows: Range<usize>,
+        fields: &Fields<T>,
+        band: &mut Band<T>,
+    ) {
+        let width = self.width;
+        let (x_range, y_range) = self.velocity_ranges(&rows);
+
+        for y in rows {
+            for x in 0..width + 1 {
+                let face = x + y * (width + 1) - x_range.start;
+                band.velocity_x[face] += self.acceleration(x, y, -1, 0, fields);
+            }
+        }
+
+        // face rows above each row of the band, and below the last one
+        for y in y_range.start / width..y_range.end / width {
+            for x in 0..width {
+                let face = x + y * width - y_range.start;
+                band.velocity_y[face] += self.acceleration(x, y, 0, -1, fields);
+            }
+        }
+    }
+
+    // change of velocity over a step on the face between (x, y) and the cell before it at
+    // (x + ox, y + oy). either may be outside the grid.
+    fn acceleration<T: Scalar>(
+        &self,
+        x: usize,
+        y: usize,
+        ox: isize,
+        oy: isize,
+        fields: &Fields<T>,
+    ) -> T {
+        let (one, two) = (T::splat(1.0), T::splat(2.0));
+        let (before, after) = (self.index(x, y, ox, oy), self.index(x, y, 0, 0));
+        let pressure = |i: Option<usize>| i.map_or(T::splat(0.0), |i| fields.current[i]);
+        let density = match (before, after) {
+            (Some(a), Some(b)) => (fields.density[a] + fields.density[b]) / two,
+            (Some(i), None) | (None, Some(i)) => fields.density[i],
+            (None, None) => one,
+        };
+
+        -T::from_f32(self.dt) / (density * T::from_f32(self.dx))
+            * (pressure(after) - pressure(before))
+    }
+
+    // p_t = -rho c^2 div v - k p, with the damping taken half before and half after
+    fn update_pressure<T: Scalar>(
+        &self,
+        rows: Range<usize>,
+        fields: &Fields<T>,
+        band: &mut Band<T>,
+    ) {
+        let width = self.width;
+        let (dx, dt) = (T::from_f32(self.dx), T::from_f32(self.dt));
+        let (one, two) = (T::splat(1.0), T::splat(2.0));
+        let Fields {
+            current,
+            space_spec,
+            density,
+            velocity_x,
+            velocity_y,
+            ..
+        } = *fields;
+        let offset = rows.start * width;
+
+        for y in rows {
+            for x in 0..width {
+                let i = x + y * width;
+                let face = x + y * (width + 1);
+                let divergence =
+                    velocity_x[face + 1] - velocity_x[face] + velocity_y[i + width] - velocity_y[i];
+
+                let (c, k) = space_spec[i];
+                let bulk = density[i] * c * c;
+                band.next[i - offset] = (current[i] * (one - k * dt / two)
+                    - dt * bulk / dx * divergence)
+                    / (one + k * dt / two);
+            }
+
+            self.probe_row(y, band, offset);
+        }
+    }
+
+    // while the row is still in cache
+    fn probe_row<T: Scalar>(&self, y: usize, band: &mut Band<T>, offset: usize) {
+        if let Some(probe) = &mut band.probe {
+            let start = y * self.width;
+            probe.add(
+                start,
+                &band.next[start - offset..start - offset + self.width],
+            );
+        }
+    }
+
+    fn edge_cell<T: Scalar>(
+        &self,
+        x: usize,
+        y: usize,
+        fields: &Fields<T>,
+        band: &mut Band<T>,
+        offset: usize,
+    ) {
+        let i = x + y * self.width;
+
+        band.next[i - offset] = match self.mur(x, y, fields) {
+            Some(value) => value,
+            None => self.interior(x, y, fields),
+        };
+
+        if self.has_pml {
+            self.update_psi(x, y, fields, band, offset);
+        }
+    }
+
+    fn interior<T: Scalar>(&self, x: usize, y: usize, fields: &Fields<T>) -> T {
+        match self.formulation {
+            Formulation::Plate { .. } => self.plate(x, y, fields),
+            _ if self.has_pml => self.pml(x, y, fields),
+            _ => self.standard(x, y, fields),
+        }
+    }
+
+    // same as the fast path, with neighbours outside the grid taken from the boundary
+    fn standard<T: Scalar>(&self, x: usize, y: usize, fields: &Fields<T>) -> T {
+        let i = x + y * self.width;
+
+        let value_previous = fields.previous[i];
+        let value_current = fields.current[i];
+
+        let (coefficient, damping) = (fields.coefficient[i], fields.damping[i]);
+        let damp = -damping * (value_current - value_previous);
+
+        T::splat(2.0) * value_current - value_previous
+            + coefficient * self.laplacian(x, y, fields)
+            + damp
+    }
+
+    // the edges apply to the laplacian like to the field, so a dirichlet edge holds the plate
+    // still without bending it and a neumann edge lets it slide
+    fn plate<T: Scalar>(&self, x: usize, y: usize, fields: &Fields<T>) -> T {
+        let width = self.width;
+        let i = x + y * width;
+        let laplacian = |field: &Window<T>, i: usize| {
+            let (x, y) = (i % width, i / width);
+            Stencil::FivePoint.apply(field[i], |ox, oy| self.at(field, x, y, ox, oy))
+        };
+        let laplacian_at = |ox: isize, oy: isize| {
+            self.index(x, y, ox, oy)
+                .map_or(T::splat(0.0), |j| laplacian(&fields.current, j))
+        };
+
+        plate_update(
+            fields.previous[i],
+            fields.current[i],
+            laplacian_at,
+            laplacian(&fields.previous, i),
+            fields.coefficient[i],
+            fields.damping[i],
+            T::from_f32(self.plate_loss()),
+        )
+    }
+
+    // laplacian times dx^2, or its variable density form
+    fn laplacian<T: Scalar>(&self, x: usize, y: usize, fields: &Fields<T>) -> T {
+        let i = x + y * self.width;
+        let value = fields.current[i];
+        let at = |ox: isize, oy: isize| self.at(&fields.current, x, y, ox, oy);
+
+        if self.variable_density {
+            // outside the grid the density doesn't change
+            let density = fields.density[i];
+            let density_at = |ox: isize, oy: isize| {
+                self.index(x, y, ox, oy)
+                    .map_or(density, |j| fields.density[j])
+            };
+            weighted_laplacian(value, density, at, density_at)
+        } else {
+            self.stencil.apply(value, at)
+        }
+    }
+
+    // u_tt + (zx + zy) u_t + zx zy u = c^2 lap u + div psi, after grote and sim.
+    // outside the layers zx, zy and psi are zero and this is the standard update.
+    fn pml<T: Scalar>(&self, x: usize, y: usize, fields: &Fields<T>) -> T {
+        let (one, two) = (T::splat(1.0), T::splat(2.0));
+        let width = self.width;
+        let (dx, dt) = (T::from_f32(self.dx), T::from_f32(self.dt));
+        let i = x + y * width;
+
+        let value_previous = fields.previous[i];
+        let value_current = fields.current[i];
+        let laplacian = self.laplacian(x, y, fields) / (dx * dx);
+
+        let psi_left = self.psi_at(&fields.psi_x, x, y, -1, 0);
+        let psi_top = self.psi_at(&fields.psi_y, x, y, 0, -1);
+        let divergence = (fields.psi_x[i] - psi_left + fields.psi_y[i] - psi_top) / dx;
+
+        let (speed, damping) = fields.space_spec[i];
+        let zeta_x = self.sigma_x(x as f32, speed);
+        let zeta_y = self.sigma_y(y as f32, speed);
+        let half = (zeta_x + zeta_y) * dt / two;
+
+        (two * value_current - value_previous * (one - half)
+            + dt * dt * (speed * speed * laplacian - zeta_x * zeta_y * value_current + divergence)
+            - damping * dt * (value_current - value_previous))
+            / (one + half)
+    }
+
+    // psi_t = -diag(zx, zy) psi + c^2 diag(zy - zx, zx - zy) grad u, on the faces
+    fn update_psi<T: Scalar>(
+        &self,
+        x: usize,
+        y: usize,
+        fields: &Fields<T>,
+        band: &mut Band<T>,
+        offset: usize,
+    ) {
+        let (one, two) = (T::splat(1.0), T::splat(2.0));
+        let (dx, dt) = (T::from_f32(self.dx), T::from_f32(self.dt));
+        let i = x + y * self.width;
+        let value = fields.current[i];
+        let c = fields.space_spec[i].0;
+
+        let zeta_x = self.sigma_x(x as f32 + 0.5, c);
+        let zeta_y = self.sigma_y(y as f32, c);
+        let gradient = (self.at(&fields.current, x, y, 1, 0) - value) / dx;
+        band.psi_x[i - offset] = (fields.psi_x[i] * (one - zeta_x * dt / two)
+            + dt * c * c * (zeta_y - zeta_x) * gradient)
+            / (one + zeta_x * dt / two);
+
+        let zeta_x = self.sigma_x(x as f32, c);
+        let zeta_y = self.sigma_y(y as f32 + 0.5, c);
+        let gradient = (self.at(&fields.current, x, y, 0, 1) - value) / dx;
+        band.psi_y[i - offset] = (fields.psi_y[i] * (one - zeta_y * dt / two)
+            + dt * c * c * (zeta_x - zeta_y) * gradient)
+            / (one + zeta_y * dt / two);
+    }
+
+    fn sigma_x<T: Scalar>(&self, position: f32, c: T) -> T {
+        let b = &self.boundary;
+        self.sigma(position, self.width, b.left, b.right, c)
+    }
+
+    fn sigma_y<T: Scalar>(&self, position: f32, c: T) -> T {
+        let b = &self.boundary;
+        self.sigma(position, self.height, b.top, b.bottom, c)
+    }
+
+    // damping profile of the layers along one axis, growing quadratically from zero at the
+    // inner face of a layer. position is in cells, faces are at half cells.
+    fn sigma<T: Scalar>(&self, position: f32, len: usize, low: Edge, high: Edge, c: T) -> T {
+        let (low, high) = (low.pml_thickness(), high.pml_thickness());
+
+        let (depth, thickness) = if position < low as f32 - 0.5 {
+            (low as f32 - 0.5 - position, low)
+        } else if position > (len - high) as f32 - 0.5 {
+            (position - (len - high) as f32 + 0.5, high)
+        } else {
+            return T::splat(0.0);
+        };
+
+        let (one, two) = (T::splat(1.0), T::splat(2.0));
+        let thickness = T::from_f32(thickness as f32);
+        let sigma_max = T::splat(3.0) * c.abs() * (one / T::from_f32(PML_REFLECTION)).ln()
+            / (two * thickness * T::from_f32(self.dx));
+        sigma_max * (T::from_f32(depth) / thickness).powi(2)
+    }
+
+    // mur boundary on an outermost cell, using the new value of the cell next to it.
+    // corners fall back to first order, left and right take precedence over top and bottom.
+    fn mur<T: Scalar>(&self, x: usize, y: usize, fields: &Fields<T>) -> Option<T> {
+        let (width, height) = (self.width, self.height);
+        let boundary = &self.boundary;
+
+        // edge, (x, y) of the neighbour inside, distance between cells along the edge
+        let (edge, (nx, ny), along) = if x == 0 && boundary.left.is_mur() {
+            (boundary.left, (x + 1, y), width)
+        } else if x + 1 == width && boundary.right.is_mur() {
+            (boundary.right, (x - 1, y), width)
+        } else if y == 0 && boundary.top.is_mur() {
+            (boundary.top, (x, y + 1), 1)
+        } else if y + 1 == height && boundary.bottom.is_mur() {
+            (boundary.bottom, (x, y - 1), 1)
+        } else {
+            return None;
+        };
+
+        let Fields {
+            previous, current, ..
+        } = *fields;
+        let i = x + y * width;
+        let n = nx + ny * width;
+
+        let (one, two) = (T::splat(1.0), T::splat(2.0));
+        let lambda = fields.space_spec[i].0.abs() * T::from_f32(self.dt) / T::from_f32(self.dx);
+        let reflection = (lambda - one) / (lambda + one);
+        let next_inside = self.interior(nx, ny, fields);
+
+        let corner = if along == 1 {
+            x == 0 || x + 1 == width
+        } else {
+            y == 0 || y + 1 == height
+        };
+
+        if edge == Edge::Mur2 && !corner {
+            let across = two / (lambda + one);
+            let along_edge = lambda * lambda / (two * (lambda + one));
+            let side =
+                |cell: usize| current[cell - along] - two * current[cell] + current[cell + along];
+
+            Some(
+                -previous[n]
+                    + reflection * (next_inside + previous[i])
+                    + across * (current[i] + current[n])
+                    + along_edge * (side(i) + side(n)),
+            )
+        } else {
+            Some(current[n] + reflection * (next_inside - current[i]))
+        }
+    }
+
+    // value of `field` at (x + ox, y + oy), which may be outside the grid
+    fn at<T: Scalar>(&self, field: &Window<T>, x: usize, y: usize, ox: isize, oy: isize) -> T {
+        self.index(x, y, ox, oy).map_or(T::splat(0.0), |i| field[i])
+    }
+
+    // index of the cell that stands in for (x + ox, y + oy), None for zero
+    fn index(&self, x: usize, y: usize, ox: isize, oy: isize) -> Option<usize> {
+        let b = &self.boundary;
+        let x = resolve(x as isize + ox, self.width, b.left, b.right)?;
+        let y = resolve(y as isize + oy, self.height, b.top, b.bottom)?;
+        Some(x + y * self.width)
+    }
+
+    // nothing flows through an edge, except around a periodic one
+    fn psi_at<T: Scalar>(&self, psi: &Window<T>, x: usize, y: usize, ox: isize, oy: isize) -> T {
+        let b = &self.boundary;
+        let wrap = |position: isize, len: usize, edge: Edge| {
+            if edge == Edge::Periodic {
+                Some(position.rem_euclid(len as isize) as usize)
+            } else if position >= 0 && position < len as isize {
+                Some(position as usize)
+            } else {
+                None
+            }
+        };
+
+        let x = wrap(x as isize + ox, self.width, b.left);
+        let y = wrap(y as isize + oy, self.height, b.top);
+
+        match (x, y) {
+            (Some(x), Some(y)) => psi[x + y * self.width],
+            _ => T::splat(0.0),
+        }
+    }
+}
+
+impl<'a, T: Scalar> Window<'a, T> {
+    pub fn new(values: &'a [T], origin: usize) -> Window<'a, T> {
+        Window {
+            values: values,
+            origin: origin,
+        }
+    }
+
+    // L::LEN values from i on, unchecked
+    #[inline(always)]
+    unsafe fn load<L: Lanes<Scalar = T>>(&self, i: usize) -> L {
+        L::load(self.values, i - self.origin)
+    }
+}
+
+impl<'a, T> Index<usize> for Window<'a, T> {
+    type Output = T;
+
+    #[inline(always)]
+    fn index(&self, i: usize) -> &T {
+        &self.values[i - self.origin]
+    }
+}
+
+// the second order update of the cells in xs, as many at once as L holds and the rest
+// one by one. every cell the stencil reads has to be inside the grid.
+#[inline(always)]
+unsafe fn row<T: Scalar, L: Lanes<Scalar = T>>(
+    kernel: &Kernel,
+    xs: Range<usize>,
+    y: usize,
+    fields: &Fields<T>,
+    next: &mut [T],
+    offset: usize,
+) {
+    let mut x = xs.start;
+    while x + L::LEN <= xs.end {
+        cells::<T, L>(kernel, x + y * kernel.width, fields, next, offset);
+        x += L::LEN;
+    }
+    for x in x..xs.end {
+        cells::<T, T>(kernel, x + y * kernel.width, fields, next, offset);
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+unsafe fn row_avx<T: Scalar>(
+    kernel: &Kernel,
+    xs: Range<usize>,
+    y: usize,
+    fields: &Fields<T>,
+    next: &mut [T],
+    offset: usize,
+) {
+    row::<T, T::Avx>(kernel, xs, y, fields, next, offset)
+}
+
+// L::LEN cells starting at i
+#[inline(always)]
+unsafe fn cells<T: Scalar, L: Lanes<Scalar = T>>(
+    kernel: &Kernel,
+    i: usize,
+    fields: &Fields<T>,
+    next: &mut [T],
+    offset: usize,
+) {
+    let Fields {
+        previous,
+        current,
+        density,
+        coefficient,
+        damping,
+        ..
+    } = *fields;
+    let width = kernel.width as isize;
+    let neighbour = |ox: isize, oy: isize| (i as isize + ox + oy * width) as usize;
+
+    let value_previous = previous.load::<L>(i);
+    let value_current = current.load::<L>(i);
+    let at = |ox: isize, oy: isize| current.load::<L>(neighbour(ox, oy));
+
+    let laplacian = if kernel.variable_density {
+        let density_at = |ox: isize, oy: isize| L::load(density, neighbour(ox, oy));
+        weighted_laplacian(value_current, L::load(density, i), at, density_at)
+    } else {
+        kernel.stencil.apply(value_current, at)
+    };
+
+    let damp = -L::load(damping, i) * (value_current - value_previous);
+
+    let value_next =
+        L::splat(2.0) * value_current - value_previous + L::load(coefficient, i) * laplacian + damp;
+    value_next.store(next, i - offset);
+}
+
+// the plate update of the cells in xs, like row
+#[inline(always)]
+unsafe fn plate_row<T: Scalar, L: Lanes<Scalar = T>>(
+    kernel: &Kernel,
+    xs: Range<usize>,
+    y: usize,
+    fields: &Fields<T>,
+    next: &mut [T],
+    offset: usize,
+) {
+    let mut x = xs.start;
+    while x + L::LEN <= xs.end {
+        plate_cells::<T, L>(kernel, x + y * kernel.width, fields, next, offset);
+        x += L::LEN;
+    }
+    for x in x..xs.end {
+        plate_cells::<T, T>(kernel, x + y * kernel.width, fields, next, offset);
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+unsafe fn plate_row_avx<T: Scalar>(
+    kernel: &Kernel,
+    xs: Range<usize>,
+    y: usize,
+    fields: &Fields<T>,
+    next: &mut [T],
+    offset: usize,
+) {
+    plate_row::<T, T::Avx>(kernel, xs, y, fields, next, offset)
+}
+
+// L::LEN plate cells starting at i, two cells away from any edge
+#[inline(always)]
+unsafe fn plate_cells<T: Scalar, L: Lanes<Scalar = T>>(
+    kernel: &Kernel,
+    i: usize,
+    fields: &Fields<T>,
+    next: &mut [T],
+    offset: usize,
+) {
+    let Fields {
+        previous,
+        current,
+        coefficient,
+        damping,
+        ..
+    } = *fields;
+    let width = kernel.width as isize;
+    let neighbour = |ox: isize, oy: isize| (i as isize + ox + oy * width) as usize;
+    let laplacian = |field: &Window<T>, ox: isize, oy: isize| {
+        let at = |dx: isize, dy: isize| field.load::<L>(neighbour(ox + dx, oy + dy));
+        Stencil::FivePoint.apply(at(0, 0), at)
+    };
+
+    let value_next = plate_update(
+        previous.load::<L>(i),
+        current.load::<L>(i),
+        |ox, oy| laplacian(&current, ox, oy),
+        laplacian(&previous, 0, 0),
+        L::load(coefficient, i),
+        L::load(damping, i),
+        L::splat(kernel.plate_loss()),
+    );
+    value_next.store(next, i - offset);
+}
+
+// u_tt = -kappa^2 lap lap u - k u_t + 2 loss lap u_t, with lap u_t taken backwards.
+// `laplacian_at` gives the laplacian times dx^2 of the cell standing in for an offset.
+#[inline(always)]
+fn plate_update<T, F>(
+    value_previous: T,
+    value: T,
+    laplacian_at: F,
+    laplacian_previous: T,
+    coefficient: T,
+    damping: T,
+    loss: T,
+) -> T
+where
+    T: Lanes,
+    F: Fn(isize, isize) -> T,
+{
+    let laplacian = laplacian_at(0, 0);
+    let biharmonic = Stencil::FivePoint.apply(laplacian, laplacian_at);
+    let damp = -damping * (value - value_previous);
+
+    T::splat(2.0) * value - value_previous - coefficient * biharmonic
+        + damp
+        + loss * (laplacian - laplacian_previous)
+}
+
+// rho div(grad p / rho) times dx^2 on the 5 point stencil.
+// each face is weighted by the inverse of the mean density on both sides of it.
+#[inline(always)]
+fn weighted_laplacian<T, F, G>(value: T, density: T, at: F, density_at: G) -> T
+where
+    T: Lanes,
+    F: Fn(isize, isize) -> T,
+    G: Fn(isize, isize) -> T,
+{
+    let face = |ox: isize, oy: isize| {
+        T::splat(2.0) / (density + density_at(ox, oy)) * (at(ox, oy) - value)
+    };
+    density * (face(-1, 0) + face(1, 0) + face(0, -1) + face(0, 1))
+}
+
+// index of the cell that stands in for `position` along one axis, None for zero
+pub(crate) fn resolve(position: isize, len: usize, low: Edge, high: Edge) -> Option<usize> {
+    let len = len as isize;
+    let (edge, mirrored) = if position < 0 {
+        (low, -position - 1)
+    } else if position >= len {
+        (high, 2 * len - 1 - position)
+    } else {
+        return Some(position as usize);
+    };
+
+    match edge {
+        Edge::Dirichlet | Edge::Pml { .. } => None,
+        // mur cells are only read by their neighbours, mirroring keeps the gradient at zero
+        Edge::Neumann | Edge::Mur1 | Edge::Mur2 => Some(mirrored.max(0).min(len - 1) as usize),
+        Edge::Periodic => Some(position.rem_euclid(len) as usize),
+    }
+}
@@ -0,0 +1,102 @@
+// what a cell is made of, in physical units
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Material {
+    // speed of sound in m/s
+    pub speed: f32,
+    // density in kg/m^3, only heard with Settings::variable_density
+    pub density: f32,
+    // how fast sound dies inside the material, per second, the dumping ratio of its cells
+    pub damping: f32,
+}
+
+// what gets into a wall is gone 60 dB in about 0.14 s. slow enough to leave the impedance,
+// and with it the reflection, as it is above a few tens of hertz.
+const WALL_DAMPING: f32 = 100.0;
+
+// a surface absorbing nothing is rigid, better left to a Neumann edge
+const MIN_ABSORPTION: f32 = 1e-4;
+
+impl Material {
+    pub fn new(speed: f32, density: f32, damping: f32) -> Material {
+        Material {
+            speed: speed,
+            density: density,
+            damping: damping,
+        }
+    }
+
+    // dry air at sea level pressure, temperature in degrees celsius
+    pub fn air(temperature: f32) -> Material {
+        let kelvin = temperature + 273.15;
+        Material {
+            speed: 331.3 * (kelvin / 273.15).sqrt(),
+            density: 101325.0 / (287.05 * kelvin),
+            damping: 0.0,
+        }
+    }
+
+    pub fn water(temperature: f32) -> Material {
+        // linear fit around room temperature
+        Material {
+            speed: 1402.4 + 4.6 * temperature,
+            density: 1000.0,
+            damping: 0.0,
+        }
+    }
+
+    // a wall whose surface takes `absorption` of the sound hitting it straight on from air at
+    // 20 degrees, its absorption coefficient alpha. how much is reflected only depends on the
+    // impedance, so walls keep the speed of the air and get their impedance from the density:
+    // real walls are far over the cfl limit at audio rates. needs Settings::variable_density.
+    pub fn wall(absorption: f32) -> Material {
+        let air = Material::air(20.0);
+        let impedance = impedance_for(absorption, air.impedance());
+        Material {
+            speed: air.speed,
+            density: impedance / air.speed,
+            damping: WALL_DAMPING,
+        }
+    }
+
+    // a wall, see wall(). real concrete carries sound at 3700 m/s, where 2 cm cells at 44.1 kHz
+    // hold 623 m/s, and absorbs about 0.01 to 0.02 of it.
+    pub fn concrete(absorption: f32) -> Material {
+        Material::wall(absorption)
+    }
+
+    // characteristic acoustic impedance in rayl
+    pub fn impedance(&self) -> f32 {
+        self.density * self.speed
+    }
+
+    // absorption coefficient of a surface of this material for sound coming straight from
+    // `from`, the fraction of the energy that isn't reflected
+    pub fn absorption(&self, from: &Material) -> f32 {
+        let (impedance, from) = (self.impedance(), from.impedance());
+        let reflection = (impedance - from) / (impedance + from);
+        1.0 - reflection * reflection
+    }
+
+    // (propagation ratio, dumping ratio) the solver uses for this material, the same at any
+    // dx and dt. the density is not part of the spec.
+    pub fn spec(&self) -> (f32, f32) {
+        (self.speed, self.damping)
+    }
+
+    pub fn from_spec(spec: (f32, f32), density: f32) -> Material {
+        Material {
+            speed: spec.0,
+            density: density,
+            damping: spec.1,
+        }
+    }
+}
+
+// impedance of a surface absorbing `absorption` of the sound coming straight from a medium of
+// `impedance`. the pressure reflection is sqrt(1 - alpha), the larger of the two impedances
+// that give it is taken, a wall is harder than the air.
+fn impedance_for(absorption: f32, impedance: f32) -> f32 {
+    let absorption = absorption.max(MIN_ABSORPTION).min(1.0);
+    let reflection = (1.0 - absorption).sqrt();
+    impedance * (1.0 + reflection) / (1.0 - reflection)
+}
@@ -1,6 +1,65 @@
+mod boundary;
+mod builder;
+mod coupling;
+mod formulation;
+mod grid;
+mod kernel;
+mod material;
+mod order;
+mod parallel;
+mod physics;
+mod scalar;
+mod shared;
+mod simd;
+mod simulator1d;
+mod simulator3d;
+mod solver;
 mod space;
+mod space3d;
+mod stencil;
+mod threaded;
+mod tiling;
+mod watch;
 mod wave_simulator;
+pub use crate::Error;
+pub use boundary::Boundary;
+pub use boundary::Boundary3D;
+pub use boundary::Edge;
+pub use boundary::End;
+pub use builder::WaveSimulatorBuilder;
+pub use coupling::Contact;
+pub use coupling::Coupling;
+pub use coupling::Domain;
+pub use coupling::Instrument;
+pub use formulation::Formulation;
+pub use material::Material;
+pub use order::Impulse;
+pub use order::Order;
+pub use order::Parameter;
+pub use order::Region;
+pub use parallel::RayonSolver;
+pub use physics::Physics;
+pub use scalar::Scalar;
+pub use simulator1d::Bow;
+pub use simulator1d::Settings1D;
+pub use simulator1d::WaveSimulator1D;
+pub use simulator3d::Settings3D;
+pub use simulator3d::WaveSimulator3D;
+pub use solver::Backend;
+pub use solver::SerialSolver;
+pub use solver::Solver;
+pub use solver::WorkerPanic;
 pub use space::Space;
-pub use wave_simulator::Order;
-pub use wave_simulator::Parameter;
+pub use space3d::Axis;
+pub use space3d::Space3D;
+pub use stencil::Stencil;
+pub use stencil::Stencil3D;
+pub use threaded::ThreadedSolver;
+pub use tiling::Tiling;
+pub use watch::Cause;
+pub use watch::Instability;
+pub use watch::Stats;
+pub use watch::Watch;
+pub use wave_simulator::Handle;
+pub use wave_simulator::Settings;
 pub use wave_simulator::WaveSimulator;
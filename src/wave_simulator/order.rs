@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use super::Material;
+
+#[derive(Copy, Clone, Debug)]
+pub enum Parameter {
+    PropagationRatio(usize, usize, f32),
+    DumpingRatio(usize, usize, f32),
+    Density(usize, usize, f32),
+}
+
+#[derive(Clone, Debug)]
+pub enum Region {
+    Rect {
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    },
+    // cells whose centre is within radius
+    Circle {
+        x: f32,
+        y: f32,
+        radius: f32,
+    },
+    // one entry per cell, row major
+    Mask(Arc<Vec<bool>>),
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum Impulse {
+    Gauss {
+        x: f32,
+        y: f32,
+        sigma: f32,
+        power: f32,
+    },
+    // spread over the four surrounding cells
+    Point {
+        x: f32,
+        y: f32,
+        power: f32,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub enum Order {
+    Change(Parameter),
+    // sets (propagation ratio, dumping ratio) of every cell in the region
+    Fill(Region, (f32, f32)),
+    // same as Fill, with the spec derived from the material. also sets the density.
+    FillMaterial(Region, Material),
+    // zeroes the field, the spec is kept
+    Reset,
+    Impulse(Impulse),
+    Pause,
+    // while paused the first one scheduled is taken at the next run, steps don't advance
+    Resume,
+}
+
+impl Region {
+    pub fn contains(&self, x: usize, y: usize, width: usize) -> bool {
+        match self {
+            Region::Rect {
+                x: left,
+                y: top,
+                width: w,
+                height: h,
+            } => x >= *left && x - left < *w && y >= *top && y - top < *h,
+            Region::Circle {
+                x: cx,
+                y: cy,
+                radius,
+            } => (x as f32 - cx).powi(2) + (y as f32 - cy).powi(2) <= radius.powi(2),
+            Region::Mask(mask) => mask.get(x + y * width).cloned().unwrap_or(false),
+        }
+    }
+}
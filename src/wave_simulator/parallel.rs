@@ -0,0 +1,94 @@
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+
+use rayon::prelude::*;
+
+use super::grid::Grid;
+use super::solver::{panic_message, Solver, WorkerPanic};
+use super::tiling::Schedule;
+use super::{Error, Scalar, Settings};
+
+// splits the rows into num_threads bands and hands them to the global rayon pool every phase.
+// no threads of its own, so it shares the cores with whatever else uses rayon.
+pub struct RayonSolver<T: Scalar = f32> {
+    grid: Grid<T>,
+    bands: Vec<Range<usize>>,
+    failed: Option<WorkerPanic>,
+}
+
+impl<T: Scalar> RayonSolver<T> {
+    pub fn new(settings: Settings) -> Result<RayonSolver<T>, Error> {
+        RayonSolver::with_space_spec(settings, None)
+    }
+
+    // starting from `space_spec` instead of the default
+    pub(crate) fn with_space_spec(
+        settings: Settings,
+        space_spec: Option<&[(T, T)]>,
+    ) -> Result<RayonSolver<T>, Error> {
+        if settings.num_threads == 0 {
+            return Err(Error::config("no threads to run on"));
+        }
+
+        let grid = Grid::new(&settings, space_spec)?;
+        let height = grid.height();
+
+        // every band needs at least one row
+        let num_bands = settings.num_threads.min(height);
+        let bands = (0..num_bands)
+            .map(|i| i * height / num_bands..(i + 1) * height / num_bands)
+            .collect();
+
+        Ok(RayonSolver {
+            grid: grid,
+            bands: bands,
+            failed: None,
+        })
+    }
+}
+
+impl<T: Scalar> Solver<T> for RayonSolver<T> {
+    fn run(&mut self, steps: usize) -> Result<(), Error> {
+        if let Some(panic) = &self.failed {
+            return Err(panic.clone().into());
+        }
+        self.grid.check()?;
+        self.grid.prepare();
+
+        let grid = &self.grid;
+        let schedule = Schedule::new(grid.kernel(), &self.bands, grid.step_count(), steps);
+        let mut result = Ok(());
+
+        for pass in schedule.tiles().flatten() {
+            // every update of a pass is done before the next pass starts
+            result = pass.par_iter().enumerate().try_for_each(|(i, update)| {
+                panic::catch_unwind(AssertUnwindSafe(|| unsafe { grid.update(update) })).map_err(
+                    |payload| WorkerPanic {
+                        worker: i,
+                        message: panic_message(payload),
+                    },
+                )
+            });
+
+            if result.is_err() {
+                break;
+            }
+        }
+
+        let checked = self.grid.advanced(steps);
+
+        if let Err(panic) = &result {
+            self.failed = Some(panic.clone());
+        }
+        result?;
+        checked
+    }
+
+    fn grid(&self) -> &Grid<T> {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut Grid<T> {
+        &mut self.grid
+    }
+}
@@ -0,0 +1,50 @@
+use super::Stencil;
+
+// physical scale of the simulation
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Physics {
+    // size of a cell in metres
+    pub dx: f32,
+    // length of a step in seconds
+    pub dt: f32,
+    // rate the mics are recorded at, in hertz
+    pub sample_rate: u32,
+}
+
+// 2 cm cells and one step per sample at 44.1 kHz, so a step is a sample of the wav
+impl Default for Physics {
+    fn default() -> Physics {
+        Physics::audio(0.02, 44100)
+    }
+}
+
+impl Physics {
+    // one step per audio sample
+    pub fn audio(dx: f32, sample_rate: u32) -> Physics {
+        Physics {
+            dx: dx,
+            dt: 1.0 / sample_rate as f32,
+            sample_rate: sample_rate,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.dx > 0.0 && self.dt > 0.0 && self.sample_rate > 0
+    }
+
+    // cfl condition, c * dt / dx <= 1 / sqrt(2) for the 5 point stencil
+    pub fn max_stable_speed(&self, stencil: Stencil) -> f32 {
+        self.dx / self.dt * stencil.max_courant()
+    }
+
+    // kappa dt / dx^2 <= 1 / 4 for a plate without loss, the loss takes some of the room
+    pub fn max_stable_stiffness(&self, loss: f32) -> f32 {
+        let area = self.dx * self.dx;
+        (area * area - 8.0 * area * loss * self.dt).max(0.0).sqrt() / (4.0 * self.dt)
+    }
+
+    // audio samples written per simulated step, may be less than one
+    pub fn samples_per_step(&self) -> f64 {
+        self.dt as f64 * self.sample_rate as f64
+    }
+}
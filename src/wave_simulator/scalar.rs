@@ -0,0 +1,87 @@
+use std::fmt::{Debug, Display};
+use std::ops::AddAssign;
+
+use super::simd::Lanes;
+#[cfg(target_arch = "x86_64")]
+use super::simd::{F32x4, F32x8, F64x2, F64x4};
+
+// number type of the fields. f64 keeps the round off of long, quiet tails down,
+// f32 is twice as fast. settings and orders stay f32 and are converted when applied.
+pub trait Scalar:
+    Lanes<Scalar = Self> + AddAssign + PartialOrd + Default + Debug + Display + Send + Sync + 'static
+{
+    // lanes of the vector paths
+    #[cfg(target_arch = "x86_64")]
+    type Sse: Lanes<Scalar = Self>;
+    #[cfg(target_arch = "x86_64")]
+    type Avx: Lanes<Scalar = Self>;
+
+    fn from_f32(value: f32) -> Self;
+    fn from_f64(value: f64) -> Self;
+    fn to_f32(self) -> f32;
+    fn to_f64(self) -> f64;
+    fn abs(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+}
+
+macro_rules! scalar {
+    ($scalar:ident, $sse:ident, $avx:ident) => {
+        impl Scalar for $scalar {
+            #[cfg(target_arch = "x86_64")]
+            type Sse = $sse;
+            #[cfg(target_arch = "x86_64")]
+            type Avx = $avx;
+
+            #[inline(always)]
+            fn from_f32(value: f32) -> $scalar {
+                value as $scalar
+            }
+
+            #[inline(always)]
+            fn from_f64(value: f64) -> $scalar {
+                value as $scalar
+            }
+
+            #[inline(always)]
+            fn to_f32(self) -> f32 {
+                self as f32
+            }
+
+            #[inline(always)]
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            #[inline(always)]
+            fn abs(self) -> $scalar {
+                $scalar::abs(self)
+            }
+
+            #[inline(always)]
+            fn sqrt(self) -> $scalar {
+                $scalar::sqrt(self)
+            }
+
+            #[inline(always)]
+            fn exp(self) -> $scalar {
+                $scalar::exp(self)
+            }
+
+            #[inline(always)]
+            fn ln(self) -> $scalar {
+                $scalar::ln(self)
+            }
+
+            #[inline(always)]
+            fn powi(self, n: i32) -> $scalar {
+                $scalar::powi(self, n)
+            }
+        }
+    };
+}
+
+scalar!(f32, F32x4, F32x8);
+scalar!(f64, F64x2, F64x4);
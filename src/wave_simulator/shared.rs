@@ -0,0 +1,41 @@
+use std::cell::UnsafeCell;
+use std::ops::Range;
+
+// a buffer shared between the simulator and its worker threads.
+// the step protocol guarantees that rows of a buffer are either read by everyone
+// or written by a single worker, never both at the same time.
+pub(crate) struct SharedBuffer<T> {
+    cells: Box<[UnsafeCell<T>]>,
+}
+
+unsafe impl<T: Send> Sync for SharedBuffer<T> {}
+
+impl<T> SharedBuffer<T> {
+    pub fn new(values: Vec<T>) -> SharedBuffer<T> {
+        SharedBuffer {
+            cells: values.into_iter().map(UnsafeCell::new).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    // caller must make sure nobody writes to the buffer while the slice is alive
+    pub unsafe fn slice(&self) -> &[T] {
+        self.slice_range(0..self.cells.len())
+    }
+
+    // caller must make sure nobody writes to the range while the slice is alive
+    pub unsafe fn slice_range(&self, range: Range<usize>) -> &[T] {
+        let cells = &self.cells[range];
+        std::slice::from_raw_parts(cells.as_ptr() as *const T, cells.len())
+    }
+
+    // caller must make sure nobody else reads or writes the range while the slice is alive
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn slice_mut(&self, range: Range<usize>) -> &mut [T] {
+        let cells = &self.cells[range];
+        std::slice::from_raw_parts_mut(cells.as_ptr() as *mut T, cells.len())
+    }
+}
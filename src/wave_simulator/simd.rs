@@ -0,0 +1,209 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+// instruction set the fast path runs on, checked once when the kernel is made
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum Simd {
+    Scalar,
+    // 4 lanes, every x86_64 cpu has it
+    Sse,
+    // 8 lanes
+    Avx,
+}
+
+impl Simd {
+    pub fn detect() -> Simd {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx") {
+                Simd::Avx
+            } else {
+                Simd::Sse
+            }
+        }
+
+        #[cfg(not(target_arch = "x86_64"))]
+        Simd::Scalar
+    }
+}
+
+// a few neighbouring cells of a row, computed at once.
+// no fused multiply add, so every lane rounds exactly like the scalar path.
+// public but out of reach, so Scalar can't be implemented outside the crate.
+pub trait Lanes:
+    Copy
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    type Scalar;
+
+    const LEN: usize;
+
+    // constants of the update, exact in either precision
+    fn splat(value: f32) -> Self;
+
+    // `from[at..at + LEN]`, unchecked
+    unsafe fn load(from: &[Self::Scalar], at: usize) -> Self;
+
+    unsafe fn store(self, to: &mut [Self::Scalar], at: usize);
+}
+
+macro_rules! scalar {
+    ($scalar:ident) => {
+        impl Lanes for $scalar {
+            type Scalar = $scalar;
+
+            const LEN: usize = 1;
+
+            #[inline(always)]
+            fn splat(value: f32) -> $scalar {
+                value as $scalar
+            }
+
+            #[inline(always)]
+            unsafe fn load(from: &[$scalar], at: usize) -> $scalar {
+                *from.get_unchecked(at)
+            }
+
+            #[inline(always)]
+            unsafe fn store(self, to: &mut [$scalar], at: usize) {
+                *to.get_unchecked_mut(at) = self;
+            }
+        }
+    };
+}
+
+scalar!(f32);
+scalar!(f64);
+
+#[cfg(target_arch = "x86_64")]
+#[derive(Copy, Clone)]
+pub struct F32x4(__m128);
+
+#[cfg(target_arch = "x86_64")]
+#[derive(Copy, Clone)]
+pub struct F32x8(__m256);
+
+#[cfg(target_arch = "x86_64")]
+#[derive(Copy, Clone)]
+pub struct F64x2(__m128d);
+
+#[cfg(target_arch = "x86_64")]
+#[derive(Copy, Clone)]
+pub struct F64x4(__m256d);
+
+// only used inside functions compiled for the instruction set they need
+macro_rules! lanes {
+    ($lanes:ident, $scalar:ident, $len:expr, $set1:ident, $loadu:ident, $storeu:ident,
+     $xor:ident, $add:ident, $sub:ident, $mul:ident, $div:ident) => {
+        #[cfg(target_arch = "x86_64")]
+        impl Lanes for $lanes {
+            type Scalar = $scalar;
+
+            const LEN: usize = $len;
+
+            #[inline(always)]
+            fn splat(value: f32) -> $lanes {
+                unsafe { $lanes($set1(value as $scalar)) }
+            }
+
+            #[inline(always)]
+            unsafe fn load(from: &[$scalar], at: usize) -> $lanes {
+                $lanes($loadu(from.as_ptr().add(at)))
+            }
+
+            #[inline(always)]
+            unsafe fn store(self, to: &mut [$scalar], at: usize) {
+                $storeu(to.as_mut_ptr().add(at), self.0)
+            }
+        }
+
+        #[cfg(target_arch = "x86_64")]
+        impl Neg for $lanes {
+            type Output = $lanes;
+
+            // flips the sign bit, like scalar negation
+            #[inline(always)]
+            fn neg(self) -> $lanes {
+                unsafe { $lanes($xor(self.0, $set1(-0.0))) }
+            }
+        }
+
+        lanes!(@op $lanes, Add, add, $add);
+        lanes!(@op $lanes, Sub, sub, $sub);
+        lanes!(@op $lanes, Mul, mul, $mul);
+        lanes!(@op $lanes, Div, div, $div);
+    };
+    (@op $lanes:ident, $trait:ident, $method:ident, $intrinsic:ident) => {
+        #[cfg(target_arch = "x86_64")]
+        impl $trait for $lanes {
+            type Output = $lanes;
+
+            #[inline(always)]
+            fn $method(self, other: $lanes) -> $lanes {
+                unsafe { $lanes($intrinsic(self.0, other.0)) }
+            }
+        }
+    };
+}
+
+lanes!(
+    F32x4,
+    f32,
+    4,
+    _mm_set1_ps,
+    _mm_loadu_ps,
+    _mm_storeu_ps,
+    _mm_xor_ps,
+    _mm_add_ps,
+    _mm_sub_ps,
+    _mm_mul_ps,
+    _mm_div_ps
+);
+
+lanes!(
+    F32x8,
+    f32,
+    8,
+    _mm256_set1_ps,
+    _mm256_loadu_ps,
+    _mm256_storeu_ps,
+    _mm256_xor_ps,
+    _mm256_add_ps,
+    _mm256_sub_ps,
+    _mm256_mul_ps,
+    _mm256_div_ps
+);
+
+lanes!(
+    F64x2,
+    f64,
+    2,
+    _mm_set1_pd,
+    _mm_loadu_pd,
+    _mm_storeu_pd,
+    _mm_xor_pd,
+    _mm_add_pd,
+    _mm_sub_pd,
+    _mm_mul_pd,
+    _mm_div_pd
+);
+
+lanes!(
+    F64x4,
+    f64,
+    4,
+    _mm256_set1_pd,
+    _mm256_loadu_pd,
+    _mm256_storeu_pd,
+    _mm256_xor_pd,
+    _mm256_add_pd,
+    _mm256_sub_pd,
+    _mm256_mul_pd,
+    _mm256_div_pd
+);
@@ -0,0 +1,226 @@
+use super::{
+    Backend, Boundary, Edge, End, Error, Physics, Scalar, Settings, Space, Watch, WaveSimulator,
+};
+
+// a string or tube, run as a grid one cell high whose top and bottom mirror the row, so the
+// 5 point stencil is the 3 point one and the propagation ratio may go up to dx / dt.
+pub struct WaveSimulator1D<T: Scalar = f32> {
+    pub length: usize,
+    wave_simulator: WaveSimulator<T>,
+    // each with the displacement under it before the last step
+    bows: Vec<(Bow, T)>,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Settings1D {
+    pub length: usize,
+    pub physics: Physics,
+    pub left: End,
+    pub right: End,
+    pub simd: bool,
+    pub watch: Option<Watch>,
+}
+
+// drags the string along at one sample, sticking while the string keeps up and slipping
+// once it doesn't
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Bow {
+    pub position: usize,
+    // speed of the bow in m/s
+    pub velocity: f32,
+    // largest acceleration the friction gives the string, in m/s^2
+    pub force: f32,
+}
+
+// how sharp the friction peaks around sticking
+const FRICTION: f32 = 100.0;
+
+// a string starts as a guitar's A string, 133 N on 6.5 g/m, about 143 m/s
+const TENSION: f32 = 133.0;
+const LINEAR_DENSITY: f32 = 0.0065;
+
+impl Settings1D {
+    // fixed ends, the rest like Settings::new
+    pub fn new(length: usize) -> Settings1D {
+        let defaults = Settings::new(length, 1);
+        Settings1D {
+            length: length,
+            physics: defaults.physics,
+            left: End::default(),
+            right: End::default(),
+            simd: defaults.simd,
+            watch: defaults.watch,
+        }
+    }
+}
+
+impl<T: Scalar> WaveSimulator1D<T> {
+    pub fn new(length: usize) -> Result<WaveSimulator1D<T>, Error> {
+        WaveSimulator1D::with_settings(Settings1D::new(length))
+    }
+
+    pub fn with_settings(settings: Settings1D) -> Result<WaveSimulator1D<T>, Error> {
+        let speed = T::from_f32((TENSION / LINEAR_DENSITY).sqrt());
+        let space_spec = vec![(speed, T::default()); settings.length];
+
+        // one row can't be split between threads
+        let wave_simulator = WaveSimulator::with_space_spec(
+            Settings {
+                num_threads: 1,
+                physics: settings.physics,
+                boundary: Boundary {
+                    left: settings.left.edge(),
+                    right: settings.right.edge(),
+                    top: Edge::Neumann,
+                    bottom: Edge::Neumann,
+                },
+                backend: Backend::Serial,
+                simd: settings.simd,
+                watch: settings.watch,
+                ..Settings::new(settings.length, 1)
+            },
+            Some(&space_spec),
+        )?;
+
+        Ok(WaveSimulator1D {
+            length: settings.length,
+            wave_simulator: wave_simulator,
+            bows: Vec::new(),
+        })
+    }
+
+    pub fn physics(&self) -> Physics {
+        self.wave_simulator.physics()
+    }
+
+    // fastest propagation ratio the physics allow, dx / dt where the string doesn't disperse
+    pub fn max_stable_speed(&self) -> f32 {
+        self.wave_simulator.max_stable_speed()
+    }
+
+    pub fn current_step(&self) -> usize {
+        self.wave_simulator.current_step()
+    }
+
+    // the string is held in a triangle peaking at `position` and let go, so whatever it was
+    // doing before stops
+    pub fn pluck(&mut self, position: f32, amplitude: f32) {
+        let last = (self.length - 1) as f32;
+        let position = position.max(0.0).min(last);
+        let shape = |x: f32| {
+            if x <= position {
+                (x + 1.0) / (position + 1.0)
+            } else {
+                (last + 1.0 - x) / (last + 1.0 - position)
+            }
+        };
+
+        let field: Vec<T> = self
+            .field()
+            .iter()
+            .enumerate()
+            .map(|(x, &value)| value + T::from_f32(amplitude * shape(x as f32)))
+            .collect();
+        self.wave_simulator.grid_mut().set_field(&field);
+    }
+
+    // bows act before every step from now on
+    pub fn add_bow(&mut self, bow: Bow) -> Result<(), Error> {
+        if bow.position >= self.length {
+            return Err(Error::config("the bow is off the string"));
+        }
+        let value = self.get(bow.position);
+        self.bows.push((bow, value));
+        Ok(())
+    }
+
+    // the velocity and force can change while playing, a bow is moved by adding it again
+    pub fn bows_mut(&mut self) -> impl Iterator<Item = &mut Bow> {
+        self.bows.iter_mut().map(|(bow, _)| bow)
+    }
+
+    pub fn remove_bows(&mut self) {
+        self.bows.clear();
+    }
+
+    // speed of a string under `tension` newtons weighing `linear_density` kg/m, for every
+    // sample. the damping is kept.
+    pub fn set_tension(&mut self, tension: f32, linear_density: f32) {
+        let speed = T::from_f32((tension / linear_density).sqrt());
+        for cell in self.space_spec_mut().iter_mut() {
+            cell.0 = speed;
+        }
+    }
+
+    pub fn step(&mut self) -> Result<(), Error> {
+        self.run(1)
+    }
+
+    pub fn run(&mut self, steps: usize) -> Result<(), Error> {
+        if self.bows.is_empty() {
+            return self.wave_simulator.run(steps);
+        }
+
+        for _ in 0..steps {
+            self.apply_bows();
+            self.wave_simulator.step()?;
+        }
+        Ok(())
+    }
+
+    // friction of the soft bow model, sqrt(2a) v exp(-a v^2 + 1/2) of the relative velocity,
+    // as a change of the displacement over one step
+    fn apply_bows(&mut self) {
+        let dt = self.physics().dt;
+        let grid = self.wave_simulator.grid_mut();
+
+        for (bow, before) in self.bows.iter_mut() {
+            let value = grid.field()[bow.position];
+            let relative = ((value - *before).to_f32() / dt) - bow.velocity;
+            let friction =
+                (2.0 * FRICTION).sqrt() * relative * (-FRICTION * relative * relative + 0.5).exp();
+
+            grid.nudge(bow.position, T::from_f32(-bow.force * friction * dt * dt));
+            *before = grid.field()[bow.position];
+        }
+    }
+
+    pub(crate) fn nudge(&mut self, cell: usize, by: T) {
+        self.wave_simulator.nudge(cell, by);
+    }
+
+    // the bows keep bowing, from a string at rest
+    pub(crate) fn reset(&mut self) -> Result<(), Error> {
+        for (_, before) in self.bows.iter_mut() {
+            *before = T::default();
+        }
+        self.wave_simulator.reset()
+    }
+
+    pub fn field(&self) -> &[T] {
+        self.wave_simulator.field()
+    }
+
+    pub fn field_mut(&mut self) -> &mut [T] {
+        self.wave_simulator.field_mut()
+    }
+
+    // wave speed and damping of every sample
+    pub fn space_spec(&self) -> &[(T, T)] {
+        self.wave_simulator.space_spec()
+    }
+
+    pub fn space_spec_mut(&mut self) -> &mut [(T, T)] {
+        self.wave_simulator.space_spec_mut()
+    }
+
+    // what a pickup at x hears
+    pub fn get(&self, x: usize) -> T {
+        self.wave_simulator.get(x, 0)
+    }
+
+    // a space one cell high
+    pub fn snapshot(&self) -> Space<T> {
+        self.wave_simulator.snapshot()
+    }
+}
@@ -0,0 +1,666 @@
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+
+use rayon::prelude::*;
+
+use super::kernel::resolve;
+use super::simd::{Lanes, Simd};
+use super::solver::{panic_message, WorkerPanic};
+use super::space3d::{self, Axis};
+use super::watch::{Monitor, Probe};
+use super::{Boundary3D, Error, Order, Physics, Scalar, Space, Space3D, Stencil3D, Watch};
+
+// the second order formulation on a volume. the planes are split into num_threads slabs along
+// z, each updated by a thread of the simulator's own rayon pool.
+pub struct WaveSimulator3D<T: Scalar = f32> {
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    physics: Physics,
+    boundary: Boundary3D,
+    stencil: Stencil3D,
+    simd: Simd,
+    previous: Vec<T>,
+    current: Vec<T>,
+    next: Vec<T>,
+    space_spec: Vec<(T, T)>,
+    // c^2 dt^2 / dx^2 and k dt of every cell, out of date while `stale`
+    coefficient: Vec<T>,
+    damping: Vec<T>,
+    stale: bool,
+    step_count: usize,
+    // planes of each slab
+    slabs: Vec<Range<usize>>,
+    pool: rayon::ThreadPool,
+    failed: Option<WorkerPanic>,
+    // none when the settings don't watch the field
+    monitor: Option<Monitor>,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Settings3D {
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    // threads of the pool, one slab each
+    pub num_threads: usize,
+    pub physics: Physics,
+    pub boundary: Boundary3D,
+    pub stencil: Stencil3D,
+    // use vector instructions where the cpu has them, the field comes out the same either way
+    pub simd: bool,
+    // stop with an error once the field turns nan or infinite, or its energy keeps growing
+    pub watch: Option<Watch>,
+}
+
+impl Settings3D {
+    // uses every core, the default physics, pressure release edges and the 7 point stencil
+    pub fn new(width: usize, height: usize, depth: usize) -> Settings3D {
+        Settings3D {
+            width: width,
+            height: height,
+            depth: depth,
+            num_threads: num_cpus::get(),
+            physics: Physics::default(),
+            boundary: Boundary3D::default(),
+            stencil: Stencil3D::default(),
+            simd: true,
+            watch: Some(Watch::default()),
+        }
+    }
+}
+
+impl<T: Scalar> WaveSimulator3D<T> {
+    pub fn new(width: usize, height: usize, depth: usize) -> Result<WaveSimulator3D<T>, Error> {
+        WaveSimulator3D::with_settings(Settings3D::new(width, height, depth))
+    }
+
+    pub fn with_settings(settings: Settings3D) -> Result<WaveSimulator3D<T>, Error> {
+        let Settings3D {
+            width,
+            height,
+            depth,
+            num_threads,
+            physics,
+            boundary,
+            stencil,
+            simd,
+            watch,
+        } = settings;
+
+        let checks = [
+            (width * height * depth == 0, "the volume has no cells"),
+            (num_threads == 0, "no threads to run on"),
+            (
+                !physics.is_valid(),
+                "dx, dt and the sample rate have to be positive",
+            ),
+            (
+                !boundary.is_valid(),
+                "3d edges are dirichlet, neumann or periodic pairs",
+            ),
+            (
+                watch.map_or(false, |watch| !watch.is_valid()),
+                "the watch needs a window and a growth over 1",
+            ),
+        ];
+        if let Some(&(_, reason)) = checks.iter().find(|&&(failed, _)| failed) {
+            return Err(Error::config(reason));
+        }
+
+        let space = Space3D::<T>::new(width, height, depth);
+        let max_speed = physics.dx / physics.dt * stencil.max_courant();
+        check_spec(&space.space_spec, width, height, max_speed, &physics)?;
+
+        // every slab needs at least one plane
+        let num_slabs = num_threads.min(depth);
+        let slabs = (0..num_slabs)
+            .map(|i| i * depth / num_slabs..(i + 1) * depth / num_slabs)
+            .collect();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_slabs)
+            .build()
+            .map_err(|error| Error::Config(error.to_string()))?;
+
+        let len = space.space.len();
+        Ok(WaveSimulator3D {
+            width: width,
+            height: height,
+            depth: depth,
+            physics: physics,
+            boundary: boundary,
+            stencil: stencil,
+            simd: if simd { Simd::detect() } else { Simd::Scalar },
+            previous: space.space.clone(),
+            current: space.space.clone(),
+            next: space.space,
+            space_spec: space.space_spec,
+            coefficient: vec![T::default(); len],
+            damping: vec![T::default(); len],
+            stale: true,
+            step_count: 0,
+            slabs: slabs,
+            pool: pool,
+            failed: None,
+            monitor: watch.map(|watch| Monitor::volume(watch, width, height)),
+        })
+    }
+
+    // at rest, like Space3D::add_gauss
+    pub fn add_gauss(&mut self, x: f32, y: f32, z: f32, sigma: f32, power: f32) {
+        let mut gauss = Space3D::<T>::new(self.width, self.height, self.depth);
+        gauss.add_gauss(x, y, z, sigma, power);
+        self.added();
+
+        for field in [&mut self.previous, &mut self.current].iter_mut() {
+            for (value, g) in field.iter_mut().zip(gauss.space.iter()) {
+                *value += *g;
+            }
+        }
+    }
+
+    // an order on the plane at depth z, the way the gui edits the slice it shows. the volume
+    // has no density and nothing that pauses it, those orders are refused like a propagation
+    // ratio over the cfl limit.
+    pub fn apply(&mut self, z: usize, order: Order) -> Result<(), Error> {
+        use super::Impulse::*;
+        use super::Parameter::*;
+        use Order::*;
+
+        if z >= self.depth {
+            return Err(Error::config("the plane is outside the volume"));
+        }
+        let (width, height) = (self.width, self.height);
+        let plane = z * width * height;
+        let index = |x: usize, y: usize| {
+            if x < width && y < height {
+                Some(plane + x + y * width)
+            } else {
+                None
+            }
+        };
+
+        match order {
+            Change(PropagationRatio(x, y, value)) => {
+                if let Some(i) = index(x, y) {
+                    self.check_stable(value)?;
+                    self.space_spec_mut()[i].0 = T::from_f32(value);
+                }
+            }
+            Change(DumpingRatio(x, y, value)) => {
+                if let Some(i) = index(x, y) {
+                    self.space_spec_mut()[i].1 = T::from_f32(value);
+                }
+            }
+            Change(Density(..)) => return Err(Error::config("the volume has no density")),
+            // the density isn't heard in a volume
+            FillMaterial(region, material) => self.apply(z, Fill(region, material.spec()))?,
+            Fill(region, spec) => {
+                self.check_stable(spec.0)?;
+                let spec = (T::from_f32(spec.0), T::from_f32(spec.1));
+                let space_spec = self.space_spec_mut();
+                for y in 0..height {
+                    for x in 0..width {
+                        if region.contains(x, y, width) {
+                            space_spec[plane + x + y * width] = spec;
+                        }
+                    }
+                }
+            }
+            Reset => {
+                for field in [&mut self.previous, &mut self.current].iter_mut() {
+                    for value in field.iter_mut() {
+                        *value = T::default();
+                    }
+                }
+                let step = self.step_count;
+                if let Some(monitor) = &mut self.monitor {
+                    monitor.clear(step);
+                }
+            }
+            Impulse(Gauss { x, y, sigma, power }) => self.add_gauss(x, y, z as f32, sigma, power),
+            Impulse(Point { x, y, power }) => {
+                self.added();
+                let (left, top) = (x.floor(), y.floor());
+                let (fx, fy) = (x - left, y - top);
+                let corners = [
+                    (0, 0, (1.0 - fx) * (1.0 - fy)),
+                    (1, 0, fx * (1.0 - fy)),
+                    (0, 1, (1.0 - fx) * fy),
+                    (1, 1, fx * fy),
+                ];
+
+                for &(ox, oy, weight) in corners.iter() {
+                    let (cx, cy) = (left + ox as f32, top + oy as f32);
+                    if cx < 0.0 || cy < 0.0 {
+                        continue;
+                    }
+                    if let Some(i) = index(cx as usize, cy as usize) {
+                        // previous and current, like add_gauss
+                        for field in [&mut self.previous, &mut self.current].iter_mut() {
+                            field[i] += T::from_f32(power * weight);
+                        }
+                    }
+                }
+            }
+            Pause | Resume => {
+                return Err(Error::config(
+                    "a volume is paused by not stepping it, there is nothing to resume",
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    fn check_stable(&self, speed: f32) -> Result<(), Error> {
+        if speed.abs() > self.max_stable_speed() {
+            return Err(Error::Config(format!(
+                "propagation ratio {} is above {}, the cfl limit for dx {}, dt {} and {:?}",
+                speed,
+                self.max_stable_speed(),
+                self.physics.dx,
+                self.physics.dt,
+                self.stencil
+            )));
+        }
+        Ok(())
+    }
+
+    // energy was added to the field, it may grow without blowing up
+    fn added(&mut self) {
+        let step = self.step_count;
+        if let Some(monitor) = &mut self.monitor {
+            monitor.reset(step);
+        }
+    }
+
+    pub fn physics(&self) -> Physics {
+        self.physics
+    }
+
+    pub fn boundary(&self) -> Boundary3D {
+        self.boundary
+    }
+
+    pub fn stencil(&self) -> Stencil3D {
+        self.stencil
+    }
+
+    // fastest propagation ratio the physics and stencil allow
+    pub fn max_stable_speed(&self) -> f32 {
+        self.physics.dx / self.physics.dt * self.stencil.max_courant()
+    }
+
+    pub fn current_step(&self) -> usize {
+        self.step_count
+    }
+
+    pub fn step(&mut self) -> Result<(), Error> {
+        self.run(1)
+    }
+
+    // once a worker panicked the field is garbage and every call fails, once it blew up every
+    // call fails until a reset
+    pub fn run(&mut self, steps: usize) -> Result<(), Error> {
+        if let Some(instability) = self.monitor.as_ref().and_then(Monitor::failed) {
+            return Err(instability.into());
+        }
+
+        for _ in 0..steps {
+            if let Some(panic) = &self.failed {
+                return Err(panic.clone().into());
+            }
+            self.prepare();
+
+            let plane = self.width * self.height;
+            let mut next = std::mem::take(&mut self.next);
+            let update = Update {
+                simulator: &*self,
+                previous: &self.previous,
+                current: &self.current,
+                zeros: vec![T::splat(0.0); self.width],
+            };
+
+            // slabs are cut off the front of next one by one, each borrowed by one thread
+            let mut rest = &mut next[..];
+            let mut outputs = Vec::new();
+            for slab in self.slabs.iter() {
+                let (output, tail) = rest.split_at_mut(slab.len() * plane);
+                outputs.push((slab.clone(), output));
+                rest = tail;
+            }
+
+            // each slab is probed right after it is written, while it is still in the cache
+            let (watched, step) = (self.monitor.is_some(), self.step_count);
+            let result: Result<Vec<_>, _> = self.pool.install(|| {
+                outputs
+                    .into_par_iter()
+                    .enumerate()
+                    .map(|(i, (planes, output))| {
+                        panic::catch_unwind(AssertUnwindSafe(|| {
+                            let origin = planes.start * plane;
+                            update.slab(planes, output);
+                            if !watched {
+                                return None;
+                            }
+                            let mut probe = Probe::new(step + 1);
+                            probe.add(origin, output);
+                            Some(probe)
+                        }))
+                        .map_err(|payload| WorkerPanic {
+                            worker: i,
+                            message: panic_message(payload),
+                        })
+                    })
+                    .collect()
+            });
+
+            self.next = next;
+            let probes = match result {
+                Ok(probes) => probes,
+                Err(panic) => {
+                    self.failed = Some(panic.clone());
+                    return Err(panic.into());
+                }
+            };
+
+            // current becomes previous, next current and the old previous is reused as next
+            std::mem::swap(&mut self.previous, &mut self.current);
+            std::mem::swap(&mut self.current, &mut self.next);
+            self.step_count += 1;
+
+            if let Some(monitor) = &mut self.monitor {
+                monitor.record(probes.into_iter().flatten().collect())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn prepare(&mut self) {
+        if !self.stale {
+            return;
+        }
+        self.stale = false;
+
+        let (dx, dt) = (T::from_f32(self.physics.dx), T::from_f32(self.physics.dt));
+        for (i, &(c, k)) in self.space_spec.iter().enumerate() {
+            self.coefficient[i] = (dt * dt) * (c * c) / (dx * dx);
+            self.damping[i] = k * dt;
+        }
+    }
+
+    pub fn field(&self) -> &[T] {
+        &self.current
+    }
+
+    pub fn field_mut(&mut self) -> &mut [T] {
+        self.added();
+        &mut self.current
+    }
+
+    pub(crate) fn nudge(&mut self, cell: usize, by: T) {
+        self.current[cell] += by;
+    }
+
+    // the reset is the same on every plane
+    pub(crate) fn reset(&mut self) -> Result<(), Error> {
+        self.apply(0, Order::Reset)
+    }
+
+    pub fn space_spec(&self) -> &[(T, T)] {
+        &self.space_spec
+    }
+
+    pub fn space_spec_mut(&mut self) -> &mut [(T, T)] {
+        self.stale = true;
+        &mut self.space_spec
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> T {
+        self.current[x + y * self.width + z * self.width * self.height]
+    }
+
+    // copy of the current field and spec
+    pub fn snapshot(&self) -> Space3D<T> {
+        Space3D {
+            space: self.current.clone(),
+            space_spec: self.space_spec.clone(),
+            width: self.width,
+            height: self.height,
+            depth: self.depth,
+        }
+    }
+
+    // one plane of the current field and spec, without copying the rest
+    pub fn slice(&self, axis: Axis, at: usize) -> Space<T> {
+        let dimensions = [self.width, self.height, self.depth];
+        let space = space3d::slice(&self.current, dimensions, axis, at);
+        let space_spec = space3d::slice(&self.space_spec, dimensions, axis, at);
+        let (width, height) = space3d::slice_size(dimensions, axis);
+
+        Space {
+            space_density: vec![T::splat(1.0); space.len()],
+            space: space,
+            space_spec: space_spec,
+            width: width,
+            height: height,
+        }
+    }
+}
+
+// what every thread of a step reads
+struct Update<'a, T: Scalar> {
+    simulator: &'a WaveSimulator3D<T>,
+    previous: &'a [T],
+    current: &'a [T],
+    // stands in for rows outside a pressure release edge
+    zeros: Vec<T>,
+}
+
+impl<'a, T: Scalar> Update<'a, T> {
+    // next field of the planes in `planes`, `output` starts at the first of them
+    fn slab(&self, planes: Range<usize>, output: &mut [T]) {
+        let WaveSimulator3D { width, height, .. } = *self.simulator;
+        let offset = planes.start * width * height;
+
+        for z in planes {
+            for y in 0..height {
+                let start = y * width + z * width * height;
+                self.row(y, z, &mut output[start - offset..start - offset + width]);
+            }
+        }
+    }
+
+    // the rows the stencil reads are looked up once, edges and all. cells whose left and right
+    // neighbours are inside the row are then updated a few at once, the rest one by one.
+    fn row(&self, y: usize, z: usize, output: &mut [T]) {
+        let simulator = self.simulator;
+        let WaveSimulator3D {
+            width,
+            height,
+            depth,
+            boundary,
+            stencil,
+            ..
+        } = *simulator;
+        let radius = stencil.radius() as isize;
+        let start = y * width + z * width * height;
+        let of_row = |field| shift(field, start, width);
+
+        let line = |oy: isize, oz: isize| {
+            let y = resolve(y as isize + oy, height, boundary.top, boundary.bottom);
+            let z = resolve(z as isize + oz, depth, boundary.front, boundary.back);
+            match (y, z) {
+                (Some(y), Some(z)) => shift(self.current, y * width + z * width * height, width),
+                _ => &self.zeros[..],
+            }
+        };
+
+        // the current field at offsets -2..=2 along y and z, offsets the stencil doesn't reach
+        // are left on the zero row
+        let current = of_row(self.current);
+        let mut ys = [&self.zeros[..]; 5];
+        let mut zs = [&self.zeros[..]; 5];
+        for o in -radius..=radius {
+            ys[(o + 2) as usize] = line(o, 0);
+            zs[(o + 2) as usize] = line(0, o);
+        }
+
+        let (previous, coefficient, damping) = (
+            of_row(self.previous),
+            of_row(&simulator.coefficient),
+            of_row(&simulator.damping),
+        );
+        let inner = if width > 2 * radius as usize {
+            radius as usize..width - radius as usize
+        } else {
+            0..0
+        };
+
+        for x in (0..inner.start).chain(inner.end.max(inner.start)..width) {
+            let at = |ox: isize, oy: isize, oz: isize| {
+                if oy != 0 {
+                    ys[(oy + 2) as usize][x]
+                } else if oz != 0 {
+                    zs[(oz + 2) as usize][x]
+                } else {
+                    match resolve(x as isize + ox, width, boundary.left, boundary.right) {
+                        Some(x) => current[x],
+                        None => T::splat(0.0),
+                    }
+                }
+            };
+            let damp = -damping[x] * (current[x] - previous[x]);
+            output[x] = T::splat(2.0) * current[x] - previous[x]
+                + coefficient[x] * stencil.apply(current[x], at)
+                + damp;
+        }
+        if inner.is_empty() {
+            return;
+        }
+
+        // every slice is cut to the cells of inner, shifted by the offset along x
+        let (first, len) = (inner.start, inner.len());
+        let cut = |line, ox: isize| shift(line, (first as isize + ox) as usize, len);
+        let mut lines = Lines {
+            previous: cut(previous, 0),
+            coefficient: cut(coefficient, 0),
+            damping: cut(damping, 0),
+            x: [cut(&self.zeros, 0); 5],
+            y: [cut(&self.zeros, 0); 5],
+            z: [cut(&self.zeros, 0); 5],
+        };
+        for o in -radius..=radius {
+            let i = (o + 2) as usize;
+            lines.x[i] = cut(current, o);
+            lines.y[i] = cut(ys[i], 0);
+            lines.z[i] = cut(zs[i], 0);
+        }
+
+        let output = &mut output[first..first + len];
+
+        // every slice holds len cells
+        unsafe {
+            match simulator.simd {
+                #[cfg(target_arch = "x86_64")]
+                Simd::Avx => row_avx(stencil, &lines, output),
+                #[cfg(target_arch = "x86_64")]
+                Simd::Sse => row::<T, T::Sse>(stencil, &lines, output),
+                _ => row::<T, T>(stencil, &lines, output),
+            }
+        }
+    }
+}
+
+// the first cell whose propagation ratio is over the cfl limit fails, like on a grid
+fn check_spec<T: Scalar>(
+    space_spec: &[(T, T)],
+    width: usize,
+    height: usize,
+    max_speed: f32,
+    physics: &Physics,
+) -> Result<(), Error> {
+    let i = match space_spec
+        .iter()
+        .position(|&(c, _)| c.to_f32().is_nan() || c.to_f32().abs() > max_speed)
+    {
+        Some(i) => i,
+        None => return Ok(()),
+    };
+    Err(Error::Config(format!(
+        "propagation ratio {} of cell ({}, {}, {}) is over the cfl limit of {} for dx {} and dt {}",
+        space_spec[i].0,
+        i % width,
+        i / width % height,
+        i / (width * height),
+        max_speed,
+        physics.dx,
+        physics.dt
+    )))
+}
+
+fn shift<T>(line: &[T], start: usize, n: usize) -> &[T] {
+    &line[start..start + n]
+}
+
+// the rows one row of the update reads, all cut to the cells written
+struct Lines<'a, T> {
+    previous: &'a [T],
+    coefficient: &'a [T],
+    damping: &'a [T],
+    // the current field at offsets -2..=2 along each axis, x[2] is the row itself
+    x: [&'a [T]; 5],
+    y: [&'a [T]; 5],
+    z: [&'a [T]; 5],
+}
+
+unsafe fn row<T: Scalar, L: Lanes<Scalar = T>>(
+    stencil: Stencil3D,
+    lines: &Lines<T>,
+    output: &mut [T],
+) {
+    let n = output.len();
+    let mut j = 0;
+    while j + L::LEN <= n {
+        cells::<T, L>(stencil, lines, j, output);
+        j += L::LEN;
+    }
+    for j in j..n {
+        cells::<T, T>(stencil, lines, j, output);
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+unsafe fn row_avx<T: Scalar>(stencil: Stencil3D, lines: &Lines<T>, output: &mut [T]) {
+    row::<T, T::Avx>(stencil, lines, output)
+}
+
+// L::LEN cells starting at j
+#[inline(always)]
+unsafe fn cells<T: Scalar, L: Lanes<Scalar = T>>(
+    stencil: Stencil3D,
+    lines: &Lines<T>,
+    j: usize,
+    output: &mut [T],
+) {
+    let value_previous = L::load(lines.previous, j);
+    let value_current = L::load(lines.x[2], j);
+    // only one of the offsets is ever nonzero
+    let at = |ox: isize, oy: isize, oz: isize| {
+        let line = if oy != 0 {
+            lines.y[(oy + 2) as usize]
+        } else if oz != 0 {
+            lines.z[(oz + 2) as usize]
+        } else {
+            lines.x[(ox + 2) as usize]
+        };
+        L::load(line, j)
+    };
+
+    let damp = -L::load(lines.damping, j) * (value_current - value_previous);
+    let next = L::splat(2.0) * value_current - value_previous
+        + L::load(lines.coefficient, j) * stencil.apply(value_current, at)
+        + damp;
+    next.store(output, j);
+}
@@ -0,0 +1,155 @@
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+use super::grid::Grid;
+use super::tiling::Schedule;
+use super::{Error, Order, Scalar, Settings, Stats};
+
+// what WaveSimulator drives: something that advances a grid by some steps.
+// grid() can't be named outside the crate, so the trait can't be implemented there either.
+pub trait Solver<T: Scalar = f32>: Send {
+    // advances `steps` time steps right away. once a worker panicked the field is garbage
+    // and every call fails with the same error, a blow up fails every call until a reset.
+    fn run(&mut self, steps: usize) -> Result<(), Error>;
+
+    fn grid(&self) -> &Grid<T>;
+
+    fn grid_mut(&mut self) -> &mut Grid<T>;
+
+    // stops whatever the solver runs on, reporting the first panic. it takes the box a
+    // WaveSimulator holds the solver in, even where there is nothing to stop.
+    #[allow(clippy::boxed_local)]
+    fn shutdown(self: Box<Self>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn step(&mut self) -> Result<(), Error> {
+        self.run(1)
+    }
+
+    fn field(&self) -> &[T] {
+        self.grid().field()
+    }
+
+    // applied right away, Pause and Resume are left to whoever drives the solver
+    fn apply(&mut self, order: Order) -> Result<(), Error> {
+        self.grid_mut().apply(order)
+    }
+
+    fn current_step(&self) -> usize {
+        self.grid().step_count()
+    }
+
+    // none unless the settings watch the field
+    fn stats(&self) -> Option<Stats> {
+        self.grid().stats()
+    }
+}
+
+// which solver WaveSimulator builds
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Backend {
+    // everything on the calling thread
+    Serial,
+    // a band of rows per thread, the threads are kept between runs
+    Threaded,
+    // bands handed to the rayon thread pool every phase
+    Rayon,
+}
+
+impl Default for Backend {
+    fn default() -> Backend {
+        Backend::Threaded
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WorkerPanic {
+    pub worker: usize,
+    pub message: String,
+}
+
+// updates the whole grid as a single band
+pub struct SerialSolver<T: Scalar = f32> {
+    grid: Grid<T>,
+    failed: Option<WorkerPanic>,
+}
+
+impl<T: Scalar> SerialSolver<T> {
+    pub fn new(settings: Settings) -> Result<SerialSolver<T>, Error> {
+        SerialSolver::with_space_spec(settings, None)
+    }
+
+    pub(crate) fn with_space_spec(
+        settings: Settings,
+        space_spec: Option<&[(T, T)]>,
+    ) -> Result<SerialSolver<T>, Error> {
+        Ok(SerialSolver {
+            grid: Grid::new(&settings, space_spec)?,
+            failed: None,
+        })
+    }
+}
+
+impl<T: Scalar> Solver<T> for SerialSolver<T> {
+    fn run(&mut self, steps: usize) -> Result<(), Error> {
+        if let Some(panic) = &self.failed {
+            return Err(panic.clone().into());
+        }
+        self.grid.check()?;
+        self.grid.prepare();
+
+        let grid = &self.grid;
+        let bands = [0..grid.height()];
+        let schedule = Schedule::new(grid.kernel(), &bands, grid.step_count(), steps);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            for pass in schedule.tiles().flatten() {
+                for update in pass.iter() {
+                    // one update at a time
+                    unsafe { grid.update(update) };
+                }
+            }
+        }));
+
+        let checked = self.grid.advanced(steps);
+
+        if let Err(payload) = result {
+            self.failed = Some(WorkerPanic {
+                worker: 0,
+                message: panic_message(payload),
+            });
+        }
+
+        match &self.failed {
+            Some(panic) => Err(panic.clone().into()),
+            None => checked,
+        }
+    }
+
+    fn grid(&self) -> &Grid<T> {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut Grid<T> {
+        &mut self.grid
+    }
+}
+
+impl fmt::Display for WorkerPanic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "worker {} panicked: {}", self.worker, self.message)
+    }
+}
+
+impl std::error::Error for WorkerPanic {}
+
+pub(crate) fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_owned()
+    }
+}
@@ -1,36 +1,48 @@
 use std::fmt::*;
 
+use super::{Material, Scalar};
+
 #[derive(Clone, Debug)]
-pub struct Space {
-    pub space: Vec<f32>,
-    pub space_spec: Vec<(f32, f32)>,
-    pub size: usize,
+pub struct Space<T = f32> {
+    pub space: Vec<T>,
+    pub space_spec: Vec<(T, T)>,
+    // only used by the variable density update
+    pub space_density: Vec<T>,
+    pub width: usize,
+    pub height: usize,
 }
 
-impl Space {
-    pub fn new(size: usize) -> Space {
+impl<T: Scalar> Space<T> {
+    // air at 20 degrees, at rest
+    pub fn new(width: usize, height: usize) -> Space<T> {
+        let (c, k) = Material::air(20.0).spec();
         Space {
-            space: vec![0.0; size * size],
+            space: vec![T::splat(0.0); width * height],
             //propagation ratio, dumping ratio
-            space_spec: vec![(0.2, 0.2); size * size],
-            size: size,
+            space_spec: vec![(T::from_f32(c), T::from_f32(k)); width * height],
+            space_density: vec![T::splat(1.0); width * height],
+            width: width,
+            height: height,
         }
     }
 
     pub fn add_gauss(&mut self, cx: f32, cy: f32, sigma: f32, power: f32) {
-        fn gauss(x: f32, sigma: f32) -> f32 {
-            1.0 / (2.0 * std::f32::consts::PI).sqrt()
+        fn gauss<T: Scalar>(x: T, sigma: T) -> T {
+            let k = T::splat;
+            k(1.0) / (k(2.0) * T::from_f64(std::f64::consts::PI)).sqrt()
                 * sigma
-                * (-(x.powi(2) / (2.0 * sigma.powi(2)))).exp()
+                * (-(x.powi(2) / (k(2.0) * sigma.powi(2)))).exp()
         }
 
-        let size = self.size;
+        let (cx, cy) = (T::from_f32(cx), T::from_f32(cy));
+        let (sigma, power) = (T::from_f32(sigma), T::from_f32(power));
 
-        for y in 0..size {
-            for x in 0..size {
+        for y in 0..self.height {
+            for x in 0..self.width {
                 let mut value = self.get(x, y);
 
-                let norm = ((cx - x as f32).powi(2) + (cy - y as f32).powi(2)).sqrt();
+                let (dx, dy) = (cx - T::from_f32(x as f32), cy - T::from_f32(y as f32));
+                let norm = (dx.powi(2) + dy.powi(2)).sqrt();
                 value += gauss(norm, sigma) * power;
 
                 self.put(x, y, value);
@@ -38,20 +50,25 @@ impl Space {
         }
     }
 
-    pub fn get(&self, x: usize, y: usize) -> f32 {
-        self.space[x + y * self.size]
+    pub fn get(&self, x: usize, y: usize) -> T {
+        self.space[x + y * self.width]
+    }
+
+    pub fn put(&mut self, x: usize, y: usize, value: T) {
+        self.space[x + y * self.width] = value;
     }
 
-    pub fn put(&mut self, x: usize, y: usize, value: f32) {
-        self.space[x + y * self.size] = value;
+    pub fn set_material(&mut self, x: usize, y: usize, material: &Material) {
+        let (c, k) = material.spec();
+        self.space_spec[x + y * self.width] = (T::from_f32(c), T::from_f32(k));
+        self.space_density[x + y * self.width] = T::from_f32(material.density);
     }
 }
 
-impl Display for Space {
+impl<T: Scalar> Display for Space<T> {
     fn fmt(&self, f: &mut Formatter) -> Result {
-        let size = self.size;
-        for y in 0..size {
-            for x in 0..size {
+        for y in 0..self.height {
+            for x in 0..self.width {
                 write!(f, "{} {} {}\n", x, y, self.get(x, y));
             }
         }
@@ -0,0 +1,132 @@
+use super::{Material, Scalar, Space};
+
+// cells are stored plane by plane, x + y * width + z * width * height
+#[derive(Clone, Debug)]
+pub struct Space3D<T = f32> {
+    pub space: Vec<T>,
+    // propagation ratio, dumping ratio
+    pub space_spec: Vec<(T, T)>,
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+}
+
+// the axis a slice is cut across
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Axis {
+    // a depth by height plane
+    X,
+    // a width by depth plane
+    Y,
+    // a width by height plane
+    Z,
+}
+
+impl<T: Scalar> Space3D<T> {
+    // air at 20 degrees, at rest
+    pub fn new(width: usize, height: usize, depth: usize) -> Space3D<T> {
+        let len = width * height * depth;
+        let (c, k) = Material::air(20.0).spec();
+        Space3D {
+            space: vec![T::splat(0.0); len],
+            space_spec: vec![(T::from_f32(c), T::from_f32(k)); len],
+            width: width,
+            height: height,
+            depth: depth,
+        }
+    }
+
+    pub fn add_gauss(&mut self, cx: f32, cy: f32, cz: f32, sigma: f32, power: f32) {
+        let (width, height, depth) = (self.width, self.height, self.depth);
+        for z in 0..depth {
+            for y in 0..height {
+                for x in 0..width {
+                    let value = gauss([x, y, z], [cx, cy, cz], sigma, power);
+                    self.space[x + y * width + z * width * height] += value;
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> T {
+        self.space[self.index(x, y, z)]
+    }
+
+    pub fn put(&mut self, x: usize, y: usize, z: usize, value: T) {
+        let i = self.index(x, y, z);
+        self.space[i] = value;
+    }
+
+    pub fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        x + y * self.width + z * self.width * self.height
+    }
+
+    pub fn set_material(&mut self, x: usize, y: usize, z: usize, material: &Material) {
+        let (speed, damping) = material.spec();
+        let i = self.index(x, y, z);
+        self.space_spec[i] = (T::from_f32(speed), T::from_f32(damping));
+    }
+
+    // one plane of the volume as a 2d space, see Axis for which way it lies
+    pub fn slice(&self, axis: Axis, at: usize) -> Space<T> {
+        let dimensions = [self.width, self.height, self.depth];
+        let space = slice(&self.space, dimensions, axis, at);
+        let space_spec = slice(&self.space_spec, dimensions, axis, at);
+        let (width, height) = slice_size(dimensions, axis);
+
+        Space {
+            space_density: vec![T::splat(1.0); space.len()],
+            space: space,
+            space_spec: space_spec,
+            width: width,
+            height: height,
+        }
+    }
+}
+
+// the normalized gaussian of Space::add_gauss, in three dimensions
+pub(crate) fn gauss<T: Scalar>(cell: [usize; 3], centre: [f32; 3], sigma: f32, power: f32) -> T {
+    let k = T::splat;
+    let sigma = T::from_f32(sigma);
+    let mut norm = k(0.0);
+    for axis in 0..3 {
+        norm += (T::from_f32(centre[axis]) - T::from_f32(cell[axis] as f32)).powi(2);
+    }
+
+    k(1.0) / (k(2.0) * T::from_f64(std::f64::consts::PI)).sqrt()
+        * sigma
+        * (-(norm / (k(2.0) * sigma.powi(2)))).exp()
+        * T::from_f32(power)
+}
+
+pub(crate) fn slice_size([width, height, depth]: [usize; 3], axis: Axis) -> (usize, usize) {
+    match axis {
+        Axis::X => (depth, height),
+        Axis::Y => (width, depth),
+        Axis::Z => (width, height),
+    }
+}
+
+// the cells of a volume laid out as `dimensions` on one plane, row by row
+pub(crate) fn slice<V: Copy>(
+    values: &[V],
+    dimensions: [usize; 3],
+    axis: Axis,
+    at: usize,
+) -> Vec<V> {
+    let [width, height, _] = dimensions;
+    let (columns, rows) = slice_size(dimensions, axis);
+    let index = |column: usize, row: usize| match axis {
+        Axis::X => at + row * width + column * width * height,
+        Axis::Y => column + at * width + row * width * height,
+        Axis::Z => column + row * width + at * width * height,
+    };
+
+    let mut plane = Vec::with_capacity(columns * rows);
+    for row in 0..rows {
+        for column in 0..columns {
+            plane.push(values[index(column, row)]);
+        }
+    }
+    plane
+}
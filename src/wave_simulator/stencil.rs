@@ -0,0 +1,125 @@
+use super::simd::Lanes;
+
+// discrete laplacian used by the update
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Stencil {
+    // second order, direct neighbours only
+    FivePoint,
+    // second order with the diagonals, waves travel at nearly the same speed in every direction
+    NinePoint,
+    // fourth order along the axes, two cells in every direction
+    FourthOrder,
+}
+
+impl Default for Stencil {
+    fn default() -> Stencil {
+        Stencil::FivePoint
+    }
+}
+
+impl Stencil {
+    // how far the stencil reaches, rows this far away from a band are read from its neighbours
+    pub fn radius(&self) -> usize {
+        match self {
+            Stencil::FivePoint | Stencil::NinePoint => 1,
+            Stencil::FourthOrder => 2,
+        }
+    }
+
+    // largest stable c * dt / dx, where the highest frequency the grid holds stops growing
+    pub fn max_courant(&self) -> f32 {
+        match self {
+            Stencil::FivePoint => 0.5f32.sqrt(),
+            Stencil::NinePoint => 0.75f32.sqrt(),
+            Stencil::FourthOrder => 0.375f32.sqrt(),
+        }
+    }
+
+    // laplacian times dx^2, `at` gives the current field at an offset from the cell
+    #[inline(always)]
+    pub(crate) fn apply<T: Lanes, F: Fn(isize, isize) -> T>(&self, value: T, at: F) -> T {
+        let k = T::splat;
+        match self {
+            Stencil::FivePoint => k(-4.0) * value + at(-1, 0) + at(1, 0) + at(0, -1) + at(0, 1),
+            Stencil::NinePoint => {
+                (k(4.0) * (at(-1, 0) + at(1, 0) + at(0, -1) + at(0, 1))
+                    + (at(-1, -1) + at(1, -1) + at(-1, 1) + at(1, 1))
+                    - k(20.0) * value)
+                    / k(6.0)
+            }
+            Stencil::FourthOrder => {
+                (k(16.0) * (at(-1, 0) + at(1, 0) + at(0, -1) + at(0, 1))
+                    - (at(-2, 0) + at(2, 0) + at(0, -2) + at(0, 2))
+                    - k(60.0) * value)
+                    / k(12.0)
+            }
+        }
+    }
+}
+
+// discrete laplacian of the 3d simulator
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Stencil3D {
+    // second order, the six direct neighbours
+    SevenPoint,
+    // fourth order along the axes, two cells in every direction
+    FourthOrder,
+}
+
+impl Default for Stencil3D {
+    fn default() -> Stencil3D {
+        Stencil3D::SevenPoint
+    }
+}
+
+impl Stencil3D {
+    // how far the stencil reaches, planes this far away from a slab are read from its neighbours
+    pub fn radius(&self) -> usize {
+        match self {
+            Stencil3D::SevenPoint => 1,
+            Stencil3D::FourthOrder => 2,
+        }
+    }
+
+    // largest stable c * dt / dx, a third of the room the 2d stencils have per axis
+    pub fn max_courant(&self) -> f32 {
+        match self {
+            Stencil3D::SevenPoint => (1.0f32 / 3.0).sqrt(),
+            Stencil3D::FourthOrder => 0.5,
+        }
+    }
+
+    // laplacian times dx^2, `at` gives the current field at an offset from the cell
+    #[inline(always)]
+    pub(crate) fn apply<T: Lanes, F: Fn(isize, isize, isize) -> T>(&self, value: T, at: F) -> T {
+        let k = T::splat;
+        match self {
+            Stencil3D::SevenPoint => {
+                k(-6.0) * value
+                    + at(-1, 0, 0)
+                    + at(1, 0, 0)
+                    + at(0, -1, 0)
+                    + at(0, 1, 0)
+                    + at(0, 0, -1)
+                    + at(0, 0, 1)
+            }
+            Stencil3D::FourthOrder => {
+                (k(16.0)
+                    * (at(-1, 0, 0)
+                        + at(1, 0, 0)
+                        + at(0, -1, 0)
+                        + at(0, 1, 0)
+                        + at(0, 0, -1)
+                        + at(0, 0, 1))
+                    - (at(-2, 0, 0)
+                        + at(2, 0, 0)
+                        + at(0, -2, 0)
+                        + at(0, 2, 0)
+                        + at(0, 0, -2)
+                        + at(0, 0, 2))
+                    - k(90.0) * value)
+                    / k(12.0)
+            }
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::*;
+use std::thread;
+
+use super::grid::Grid;
+use super::solver::{panic_message, Solver, WorkerPanic};
+use super::tiling::Schedule;
+use super::{Error, Scalar, Settings};
+
+// a band of rows per thread, the threads live as long as the solver
+pub struct ThreadedSolver<T: Scalar = f32> {
+    grid: Grid<T>,
+    control: Arc<Control>,
+    workers: Vec<thread::JoinHandle<()>>,
+}
+
+// workers wait on `start` until the solver hands them a number of steps,
+// run the passes of the schedule in lock step on `step` and meet the solver on `start`
+// again when done
+struct Control {
+    // 0 tells the workers to exit
+    steps: AtomicUsize,
+    // number of steps taken before this run
+    first_step: AtomicUsize,
+    start: Barrier,
+    step: Barrier,
+    // set once a worker panicked, the others stop updating but keep meeting on the barriers
+    failed: AtomicBool,
+    panics: Mutex<Vec<WorkerPanic>>,
+    // a worker told to panic in its next update, only in the crate's own tests
+    #[cfg(test)]
+    doomed: AtomicUsize,
+}
+
+impl<T: Scalar> ThreadedSolver<T> {
+    pub fn new(settings: Settings) -> Result<ThreadedSolver<T>, Error> {
+        ThreadedSolver::with_space_spec(settings, None)
+    }
+
+    // starting from `space_spec` instead of the default
+    pub(crate) fn with_space_spec(
+        settings: Settings,
+        space_spec: Option<&[(T, T)]>,
+    ) -> Result<ThreadedSolver<T>, Error> {
+        if settings.num_threads == 0 {
+            return Err(Error::config("no threads to run on"));
+        }
+
+        let grid = Grid::new(&settings, space_spec)?;
+        let height = grid.height();
+
+        // every thread needs at least one row
+        let num_threads = settings.num_threads.min(height);
+
+        let control = Arc::new(Control {
+            steps: AtomicUsize::new(0),
+            first_step: AtomicUsize::new(0),
+            start: Barrier::new(num_threads + 1),
+            step: Barrier::new(num_threads),
+            failed: AtomicBool::new(false),
+            panics: Mutex::new(Vec::new()),
+            #[cfg(test)]
+            doomed: AtomicUsize::new(usize::MAX),
+        });
+
+        // rows are split as evenly as possible, bands differ by at most one row
+        let bands: Vec<_> = (0..num_threads)
+            .map(|i| i * height / num_threads..(i + 1) * height / num_threads)
+            .collect();
+
+        let mut workers = Vec::new();
+
+        for i in 0..num_threads {
+            let bands = bands.clone();
+            let kernel = grid.kernel().clone();
+            let buffers = Arc::clone(grid.buffers());
+            let control = Arc::clone(&control);
+
+            workers.push(thread::spawn(move || {
+                loop {
+                    control.start.wait();
+
+                    let steps = control.steps.load(Ordering::SeqCst);
+                    if steps == 0 {
+                        break;
+                    }
+                    let first_step = control.first_step.load(Ordering::SeqCst);
+
+                    let schedule = Schedule::new(&kernel, &bands, first_step, steps);
+
+                    for (k, pass) in schedule.tiles().flatten().enumerate() {
+                        // every update of a pass has to be written before the next one reads it
+                        if k > 0 {
+                            control.step.wait();
+                        }
+                        if control.failed.load(Ordering::SeqCst) {
+                            continue;
+                        }
+
+                        // without tiling worker i always takes band i
+                        for update in pass.iter().skip(i).step_by(num_threads) {
+                            // the solver does not touch the buffers until the workers are
+                            // back on the start barrier, and only one pass runs at a time
+                            let result = panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+                                control.doom(i);
+                                buffers.update(&kernel, update)
+                            }));
+
+                            if let Err(payload) = result {
+                                // nothing panics while holding it, the list is fine either way
+                                let mut panics = control
+                                    .panics
+                                    .lock()
+                                    .unwrap_or_else(PoisonError::into_inner);
+                                panics.push(WorkerPanic {
+                                    worker: i,
+                                    message: panic_message(payload),
+                                });
+                                control.failed.store(true, Ordering::SeqCst);
+                                break;
+                            }
+                        }
+                    }
+
+                    control.start.wait();
+                }
+            }));
+        }
+
+        Ok(ThreadedSolver {
+            grid: grid,
+            control: control,
+            workers: workers,
+        })
+    }
+
+    fn stop(&mut self) -> Result<(), Error> {
+        if self.workers.is_empty() {
+            return Ok(());
+        }
+
+        // zero steps tells the workers to exit
+        self.control.steps.store(0, Ordering::SeqCst);
+        self.control.start.wait();
+
+        let mut result = self.first_panic();
+
+        for (i, worker) in self.workers.drain(..).enumerate() {
+            if let Err(payload) = worker.join() {
+                if result.is_ok() {
+                    result = Err(Error::Worker(WorkerPanic {
+                        worker: i,
+                        message: panic_message(payload),
+                    }));
+                }
+            }
+        }
+
+        result
+    }
+
+    fn first_panic(&self) -> Result<(), Error> {
+        match self.control.panics.lock()?.first() {
+            Some(panic) => Err(panic.clone().into()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Control {
+    #[cfg(test)]
+    fn doom(&self, worker: usize) {
+        if self.doomed.load(Ordering::SeqCst) == worker {
+            panic!("worker {} was told to fail", worker);
+        }
+    }
+
+    #[cfg(not(test))]
+    #[inline(always)]
+    fn doom(&self, _worker: usize) {}
+}
+
+impl<T: Scalar> Solver<T> for ThreadedSolver<T> {
+    fn run(&mut self, steps: usize) -> Result<(), Error> {
+        self.first_panic()?;
+        self.grid.check()?;
+        if steps == 0 {
+            return Ok(());
+        }
+        self.grid.prepare();
+
+        self.control.steps.store(steps, Ordering::SeqCst);
+        self.control
+            .first_step
+            .store(self.grid.step_count(), Ordering::SeqCst);
+
+        // let the workers go, then wait for them to finish
+        self.control.start.wait();
+        self.control.start.wait();
+
+        let checked = self.grid.advanced(steps);
+
+        self.first_panic()?;
+        checked
+    }
+
+    fn grid(&self) -> &Grid<T> {
+        &self.grid
+    }
+
+    fn grid_mut(&mut self) -> &mut Grid<T> {
+        &mut self.grid
+    }
+
+    // joins the workers, reporting the first one that panicked
+    fn shutdown(mut self: Box<Self>) -> Result<(), Error> {
+        self.stop()
+    }
+}
+
+impl<T: Scalar> Drop for ThreadedSolver<T> {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panics_come_back_from_every_run() {
+        let settings = Settings {
+            num_threads: 3,
+            ..Settings::new(24, 18)
+        };
+        let mut solver = ThreadedSolver::<f32>::new(settings).unwrap();
+        solver.run(5).unwrap();
+
+        solver.control.doomed.store(1, Ordering::SeqCst);
+        for _ in 0..2 {
+            match solver.run(5) {
+                Err(Error::Worker(WorkerPanic { worker: 1, message })) => {
+                    assert!(message.contains("told to fail"), "{}", message)
+                }
+                other => panic!("{:?}", other),
+            }
+        }
+        match Box::new(solver).shutdown() {
+            Err(Error::Worker(WorkerPanic { worker: 1, .. })) => (),
+            other => panic!("{:?}", other),
+        }
+    }
+}
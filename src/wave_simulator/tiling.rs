@@ -0,0 +1,128 @@
+use std::ops::Range;
+
+use super::kernel::Kernel;
+
+// advances blocks of rows several steps at a time while they are still in cache.
+// the blocks of later steps are shifted up by the rows a step reads beyond a cell, so each
+// one only needs what the blocks above it and its own earlier steps already wrote.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tiling {
+    // time steps per tile
+    pub steps: usize,
+    // rows per block
+    pub rows: usize,
+}
+
+impl Tiling {
+    pub fn new(steps: usize, rows: usize) -> Tiling {
+        Tiling {
+            steps: steps,
+            rows: rows,
+        }
+    }
+
+    // 8 steps over blocks of about 512 KiB of fields and coefficients
+    pub fn for_width(width: usize) -> Tiling {
+        // 3 fields, space_spec, coefficient and damping
+        let row_bytes = width * 4 * 7;
+        Tiling::new(8, (512 * 1024 / row_bytes.max(1)).max(4))
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.steps > 0 && self.rows > 0
+    }
+}
+
+// one phase of one step on some rows
+#[derive(Clone, Debug)]
+pub(crate) struct Update {
+    pub step: usize,
+    pub phase: usize,
+    pub rows: Range<usize>,
+}
+
+// the updates of a run, in passes. the updates of a pass don't depend on each other,
+// every pass has to finish before the next one starts.
+pub(crate) struct Schedule<'a> {
+    kernel: &'a Kernel,
+    bands: &'a [Range<usize>],
+    first_step: usize,
+    steps: usize,
+}
+
+impl<'a> Schedule<'a> {
+    // without tiling every band is updated once per phase of a step
+    pub fn new(
+        kernel: &'a Kernel,
+        bands: &'a [Range<usize>],
+        first_step: usize,
+        steps: usize,
+    ) -> Schedule<'a> {
+        Schedule {
+            kernel: kernel,
+            bands: bands,
+            first_step: first_step,
+            steps: steps,
+        }
+    }
+
+    // passes tile by tile, so a long run never holds all of them
+    pub fn tiles(&self) -> impl Iterator<Item = Vec<Vec<Update>>> + '_ {
+        let tile_steps = self.kernel.tiling().map_or(1, |tiling| tiling.steps);
+        let end = self.first_step + self.steps;
+
+        (self.first_step..end)
+            .step_by(tile_steps)
+            .map(move |first| match self.kernel.tiling() {
+                Some(tiling) => self.wavefront(tiling.rows, first, tile_steps.min(end - first)),
+                None => self.plain(first),
+            })
+    }
+
+    fn plain(&self, step: usize) -> Vec<Vec<Update>> {
+        (0..self.kernel.phases())
+            .map(|phase| {
+                self.bands
+                    .iter()
+                    .map(|rows| Update {
+                        step: step,
+                        phase: phase,
+                        rows: rows.clone(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // block j of step first + s covers rows j * rows - s * skew up to the next block.
+    // it needs block j - 1 of the step before, so the blocks along an anti diagonal of
+    // (block, step) can run at the same time.
+    fn wavefront(&self, rows: usize, first: usize, steps: usize) -> Vec<Vec<Update>> {
+        let height = self.kernel.height();
+        let skew = self.kernel.reach();
+        let blocks = (height + (steps - 1) * skew + rows - 1) / rows;
+
+        (0..blocks + steps - 1)
+            .map(|diagonal| {
+                (diagonal.saturating_sub(steps - 1)..blocks.min(diagonal + 1))
+                    .filter_map(|block| {
+                        let s = diagonal - block;
+                        let shift = s * skew;
+                        let start = (block * rows).saturating_sub(shift).min(height);
+                        let end = ((block + 1) * rows).saturating_sub(shift).min(height);
+                        if start < end {
+                            Some(Update {
+                                step: first + s,
+                                phase: 0,
+                                rows: start..end,
+                            })
+                        } else {
+                            None
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|pass| !pass.is_empty())
+            .collect()
+    }
+}
@@ -0,0 +1,329 @@
+use std::fmt;
+
+use super::Scalar;
+
+// how the simulator checks that the field doesn't blow up, see Settings::watch
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Watch {
+    // steps the baseline is taken over, once something was added to the field
+    pub window: usize,
+    // how many times the energy may grow over the baseline, the largest energy of the first
+    // window, until something is added again
+    pub growth: f64,
+}
+
+impl Default for Watch {
+    fn default() -> Watch {
+        Watch {
+            window: 64,
+            growth: 1000.0,
+        }
+    }
+}
+
+impl Watch {
+    pub fn is_valid(&self) -> bool {
+        self.window > 0 && self.growth > 1.0
+    }
+}
+
+// the field right after a step
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Stats {
+    // steps taken
+    pub step: usize,
+    // sum of the squared field
+    pub energy: f64,
+    // largest absolute value of the field after the step and the cell it is in
+    pub peak: f64,
+    pub peak_at: (usize, usize),
+}
+
+// why the simulator stopped, the field is garbage from here on
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Instability {
+    // steps taken when it was found
+    pub step: usize,
+    pub x: usize,
+    pub y: usize,
+    // plane of a volume, none on a grid
+    pub z: Option<usize>,
+    pub cause: Cause,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Cause {
+    // the cell is nan or infinite
+    NotFinite,
+    // the energy grew this many times over the baseline, infinitely if it overflowed while
+    // the field was still finite. the cell is the peak of that step.
+    Growth(f64),
+}
+
+// what one update wrote, the energy of a step is put together from the probes of its bands
+#[derive(Clone, Debug)]
+pub(crate) struct Probe {
+    step: usize,
+    energy: f64,
+    // largest absolute value and its index in the whole field
+    peak: f64,
+    peak_at: usize,
+    not_finite: Option<usize>,
+}
+
+// keeps the energy of the last windows, owned by the grid
+#[derive(Clone, Debug)]
+pub(crate) struct Monitor {
+    watch: Watch,
+    width: usize,
+    // rows of a plane when the field is a volume
+    height: Option<usize>,
+    last: Option<Stats>,
+    // largest energy of the first window that had any since something was added to the field
+    baseline: Option<f64>,
+    window_peak: f64,
+    window_start: usize,
+    failed: Option<Instability>,
+}
+
+impl Probe {
+    pub fn new(step: usize) -> Probe {
+        Probe {
+            step: step,
+            energy: 0.0,
+            peak: 0.0,
+            peak_at: 0,
+            not_finite: None,
+        }
+    }
+
+    // a row of the field starting at cell `origin`
+    pub fn add<T: Scalar>(&mut self, origin: usize, row: &[T]) {
+        let (energy, peak) = energy_and_peak(row);
+        self.energy += energy;
+
+        // the row is only searched again when it holds a new peak
+        if peak.to_f64() > self.peak {
+            if let Some(i) = row.iter().position(|value| value.abs() >= peak) {
+                self.peak = peak.to_f64();
+                self.peak_at = origin + i;
+            }
+        }
+
+        // nan and infinity end up in the energy, so does a finite field too loud to square
+        if !energy.is_finite() && self.not_finite.is_none() {
+            let i = row.iter().position(|value| !value.to_f64().is_finite());
+            self.not_finite = i.map(|i| origin + i);
+        }
+    }
+}
+
+// sum of squares and largest absolute value, in independent lanes so the compiler can
+// vectorize it. nan is left out of the peak.
+fn energy_and_peak<T: Scalar>(values: &[T]) -> (f64, T) {
+    const LANES: usize = 8;
+    let mut energy = [T::splat(0.0); LANES];
+    let mut peak = [T::splat(0.0); LANES];
+
+    let chunks = values.chunks_exact(LANES);
+    let rest = chunks.remainder();
+    for chunk in chunks {
+        for j in 0..LANES {
+            energy[j] += chunk[j] * chunk[j];
+            let value = chunk[j].abs();
+            if value > peak[j] {
+                peak[j] = value;
+            }
+        }
+    }
+    for (j, &value) in rest.iter().enumerate() {
+        energy[j] += value * value;
+        if value.abs() > peak[j] {
+            peak[j] = value.abs();
+        }
+    }
+
+    let peak = peak.iter().fold(
+        T::splat(0.0),
+        |peak, &value| if value > peak { value } else { peak },
+    );
+    (energy.iter().map(|energy| energy.to_f64()).sum(), peak)
+}
+
+impl Monitor {
+    pub fn new(watch: Watch, width: usize) -> Monitor {
+        Monitor {
+            watch: watch,
+            width: width,
+            height: None,
+            last: None,
+            baseline: None,
+            window_peak: 0.0,
+            window_start: 0,
+            failed: None,
+        }
+    }
+
+    // planes of width by height cells
+    pub fn volume(watch: Watch, width: usize, height: usize) -> Monitor {
+        Monitor {
+            height: Some(height),
+            ..Monitor::new(watch, width)
+        }
+    }
+
+    pub fn watch(&self) -> Watch {
+        self.watch
+    }
+
+    // the last step checked
+    pub fn stats(&self) -> Option<Stats> {
+        self.last
+    }
+
+    pub fn failed(&self) -> Option<Instability> {
+        self.failed
+    }
+
+    // something was added to the field at `step`, growth is measured from here on
+    pub fn reset(&mut self, step: usize) {
+        self.baseline = None;
+        self.window_peak = 0.0;
+        self.window_start = step;
+    }
+
+    // the field is clean again
+    pub fn clear(&mut self, step: usize) {
+        self.failed = None;
+        self.reset(step);
+    }
+
+    // checks the steps of a run in order, stopping at the first one that went wrong
+    pub fn record(&mut self, mut probes: Vec<Probe>) -> Result<(), Instability> {
+        if let Some(instability) = self.failed {
+            return Err(instability);
+        }
+
+        // the bands of a step are next to each other, in no particular order
+        probes.sort_by_key(|probe| probe.step);
+        let mut probes = probes.into_iter().peekable();
+
+        while let Some(first) = probes.next() {
+            let mut step = first;
+            while let Some(probe) = probes.peek() {
+                if probe.step != step.step {
+                    break;
+                }
+                step = merge(step, probes.next().unwrap());
+            }
+
+            if let Err(instability) = self.check(step) {
+                self.failed = Some(instability);
+                return Err(instability);
+            }
+        }
+        Ok(())
+    }
+
+    fn check(&mut self, probe: Probe) -> Result<(), Instability> {
+        let (width, height) = (self.width, self.height);
+        let fail = |i: usize, cause: Cause| {
+            let (x, y, z) = cell(i, width, height);
+            Err(Instability {
+                step: probe.step,
+                x: x,
+                y: y,
+                z: z,
+                cause: cause,
+            })
+        };
+
+        if let Some(i) = probe.not_finite {
+            return fail(i, Cause::NotFinite);
+        }
+
+        let (peak_x, peak_y, _) = cell(probe.peak_at, width, height);
+        self.last = Some(Stats {
+            step: probe.step,
+            energy: probe.energy,
+            peak: probe.peak,
+            peak_at: (peak_x, peak_y),
+        });
+
+        if !probe.energy.is_finite() {
+            return fail(probe.peak_at, Cause::Growth(std::f64::INFINITY));
+        }
+
+        // growth is measured from the same baseline all along, slow growth adds up too
+        if let Some(baseline) = self.baseline {
+            if probe.energy > baseline * self.watch.growth {
+                return fail(probe.peak_at, Cause::Growth(probe.energy / baseline));
+            }
+            return Ok(());
+        }
+
+        self.window_peak = self.window_peak.max(probe.energy);
+        if probe.step >= self.window_start + self.watch.window {
+            // a silent field has nothing to grow from, the next window is tried
+            if self.window_peak > 0.0 {
+                self.baseline = Some(self.window_peak);
+            }
+            self.window_peak = 0.0;
+            self.window_start = probe.step;
+        }
+        Ok(())
+    }
+}
+
+// x, y and the plane of cell `i` of the field
+fn cell(i: usize, width: usize, height: Option<usize>) -> (usize, usize, Option<usize>) {
+    match height {
+        Some(height) => (i % width, i / width % height, Some(i / (width * height))),
+        None => (i % width, i / width, None),
+    }
+}
+
+fn merge(a: Probe, b: Probe) -> Probe {
+    let not_finite = match (a.not_finite, b.not_finite) {
+        (Some(i), Some(j)) => Some(i.min(j)),
+        (i, j) => i.or(j),
+    };
+
+    let (peak, peak_at) = if b.peak > a.peak {
+        (b.peak, b.peak_at)
+    } else {
+        (a.peak, a.peak_at)
+    };
+
+    Probe {
+        step: a.step,
+        energy: a.energy + b.energy,
+        peak: peak,
+        peak_at: peak_at,
+        not_finite: not_finite,
+    }
+}
+
+impl fmt::Display for Instability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cell = match self.z {
+            Some(z) => format!("({}, {}, {})", self.x, self.y, z),
+            None => format!("({}, {})", self.x, self.y),
+        };
+        match self.cause {
+            Cause::NotFinite => write!(f, "cell {} is not finite after step {}", cell, self.step),
+            Cause::Growth(factor) if factor.is_infinite() => write!(
+                f,
+                "energy overflowed by step {}, peaking at cell {}",
+                self.step, cell
+            ),
+            Cause::Growth(factor) => write!(
+                f,
+                "energy grew {:.0} times by step {}, peaking at cell {}",
+                factor, self.step, cell
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Instability {}
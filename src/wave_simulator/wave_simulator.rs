@@ -1,290 +1,370 @@
-use crate::NUM_THREADS;
-
-use std::fmt::*;
 use std::sync::*;
-use std::thread;
-
-use super::Space;
 
-#[derive(Clone)]
-pub struct WaveSimulator {
-    pub space_previous: Arc<Mutex<Space>>,
-    pub space_current: Arc<Mutex<Space>>,
-    pub space_next: Arc<Mutex<Space>>,
-    pub space_size: usize,
-    tx_update: Vec<mpsc::Sender<(usize, mpsc::Sender<usize>)>>,
-    pub tx_order: Vec<mpsc::Sender<(Order)>>,
+use super::grid::Grid;
+use super::{
+    Backend, Boundary, Error, Formulation, Order, Physics, RayonSolver, Scalar, SerialSolver,
+    Solver, Space, Stats, Stencil, ThreadedSolver, Tiling, Watch,
+};
+
+// f32 unless asked otherwise, orders and settings are f32 either way
+pub struct WaveSimulator<T: Scalar = f32> {
+    pub width: usize,
+    pub height: usize,
+    solver: Box<dyn Solver<T>>,
+    paused: bool,
+    // orders are applied between steps, when no worker is running.
+    // an order without a step is applied before the next step.
+    // handles send theirs here, the simulator's own go straight to scheduled.
+    tx_order: mpsc::Sender<(Option<usize>, Order)>,
+    rx_order: mpsc::Receiver<(Option<usize>, Order)>,
+    // sorted by step, orders for the same step keep the order they were sent in
+    scheduled: Vec<(usize, Order)>,
 }
 
 #[derive(Copy, Clone, Debug)]
-pub enum Parameter {
-    PropagationRatio(usize, usize, f32),
-    DumpingRatio(usize, usize, f32),
+pub struct Settings {
+    pub width: usize,
+    pub height: usize,
+    // threads of the threaded backend, bands of the rayon one
+    pub num_threads: usize,
+    pub physics: Physics,
+    pub boundary: Boundary,
+    pub stencil: Stencil,
+    // solve p_tt = rho c^2 div(grad p / rho) instead of p_tt = c^2 lap p, so interfaces
+    // between materials reflect by their impedance. needs the 5 point stencil.
+    pub variable_density: bool,
+    // the staggered and plate formulations take neither mur nor pml edges, and only the 5 point
+    // stencil
+    pub formulation: Formulation,
+    pub backend: Backend,
+    // use vector instructions for the inside of the grid when the cpu has them.
+    // the field comes out the same either way.
+    pub simd: bool,
+    // advance several steps per block of rows, for grids too large for the cache.
+    // ignored by the staggered and plate formulations and with periodic top and bottom edges.
+    pub tiling: Option<Tiling>,
+    // stop with an error once the field turns nan or infinite, or its energy keeps growing
+    pub watch: Option<Watch>,
 }
 
-#[derive(Copy, Clone, Debug)]
-pub enum Order {
-    Change(Parameter),
+// cheap to clone, can be sent to other threads to control a running simulator
+#[derive(Clone)]
+pub struct Handle {
+    tx_order: mpsc::Sender<(Option<usize>, Order)>,
 }
 
-impl WaveSimulator {
-    pub fn new(size: usize) -> std::result::Result<WaveSimulator, ()> {
-        if size % NUM_THREADS != 0 {
-            return Err(());
+impl Settings {
+    // uses every core, the default physics, pressure release edges and the second order
+    // formulation with the 5 point stencil
+    pub fn new(width: usize, height: usize) -> Settings {
+        Settings {
+            width: width,
+            height: height,
+            num_threads: num_cpus::get(),
+            physics: Physics::default(),
+            boundary: Boundary::default(),
+            stencil: Stencil::default(),
+            variable_density: false,
+            formulation: Formulation::default(),
+            backend: Backend::default(),
+            simd: true,
+            tiling: None,
+            watch: Some(Watch::default()),
         }
+    }
+}
 
-        let space_current = Arc::new(Mutex::new(Space::new(size)));
-        let space_previous = Arc::new(Mutex::new(Space::new(size)));
-        let space_next = Arc::new(Mutex::new(Space::new(size)));
-
-        let len_per_thread = size / NUM_THREADS;
-        let mut tx_update_vec: Vec<mpsc::Sender<(usize, mpsc::Sender<usize>)>> = Vec::new();
-        let mut tx_order_vec: Vec<mpsc::Sender<(Order)>> = Vec::new();
-
-        for _ in 0..NUM_THREADS {
-            let (tx_update, rx_update) = mpsc::channel();
-            tx_update_vec.push(tx_update);
-            let (tx_order, rx_order) = mpsc::channel();
-            tx_order_vec.push(tx_order);
-
-            let space_current = Arc::clone(&space_current);
-            let space_previous = Arc::clone(&space_previous);
-            let space_next = Arc::clone(&space_next);
-
-            thread::spawn(move || {
-                const dx: f32 = 0.1;
-                const dt: f32 = 1.0 / 60.0;
-                //let mut c: f32 = 0.2;
-                //let mut coefficient: f32 = (dt * dt) / (dx * dx); // * c *  c
-                //let mut k: f32 = 0.1;
-
-                while let Ok((i, tx_update)) = rx_update.recv() {
-                    if let Ok(order) = rx_order.try_recv() {
-                        use Order::*;
-                        use Parameter::*;
-                        match order {
-                            Change(param) => match param {
-                                /*
-                                PropagationRatio(x, y, value) => {
-                                    //c = value;
-                                }
-                                DumpingRatio(x, y, value) => {
-                                    //k = value;
-                                }
-                                */
-                                _ => {}
-                            },
-                            _ => {}
-                        }
-                    }
-
-                    let mut y_start = i * len_per_thread * size;
-                    let mut y_end = (i + 1) * len_per_thread * size;
-
-                    if i == 0 {
-                        y_start += size
-                    }
-
-                    if i == NUM_THREADS - 1 {
-                        y_end -= size
-                    }
-                    y_start -= size;
-                    y_end += size;
-
-                    let mut my_space_spec;
-                    let mut my_current;
-
-                    {
-                        let current_space = space_current.lock().unwrap();
-                        my_current = current_space.space[y_start..y_end].to_vec().clone();
-                        my_space_spec = current_space.space_spec[y_start..y_end].to_vec().clone();
-                    }
-
-                    if i == 0 {
-                        let tmp = my_current;
-                        my_current = vec![0.0; size];
-                        my_current.extend_from_slice(tmp.as_slice());
-                    }
-                    if i == NUM_THREADS - 1 {
-                        my_current.extend_from_slice(vec![0.0; size].as_slice());
-                    }
-
-                    let mut my_previous;
-
-                    {
-                        let previous_space = space_previous.lock().unwrap();
-                        my_previous = previous_space.space[y_start..y_end].to_vec().clone();
-                    }
-
-                    if i == 0 {
-                        let tmp = my_previous;
-                        my_previous = vec![0.0; size];
-                        my_previous.extend_from_slice(tmp.as_slice());
-                    }
-                    if i == NUM_THREADS - 1 {
-                        my_previous.extend_from_slice(vec![0.0; size].as_slice());
-                    }
-
-                    let mut my_next;
-
-                    {
-                        let next_space = space_next.lock().unwrap();
-                        my_next = next_space.space[y_start..y_end].to_vec().clone();
-                    }
-
-                    if i == 0 {
-                        let tmp = my_next;
-                        my_next = vec![0.0; size];
-                        my_next.extend_from_slice(tmp.as_slice());
-                    }
-                    if i == NUM_THREADS - 1 {
-                        my_next.extend_from_slice(vec![0.0; size].as_slice());
-                    }
-
-                    y_start = 1;
-                    y_end = len_per_thread + 1;
-
-                    // update each cells
-                    for y in y_start..y_end {
-                        for x in 1..size - 1 {
-                            let value_previous = my_previous[x + y * size];
-                            let value_current = my_current[x + y * size];
-
-                            let value_left = my_current[x - 1 + y * size];
-                            let value_right = my_current[x + 1 + y * size];
-                            let value_top = my_current[x + (y - 1) * size];
-                            let value_bottom = my_current[x + (y + 1) * size];
-
-                            let (c, k) = my_space_spec[x + y * size];
-                            let coefficient = (dt * dt) * (c * c) / (dx * dx);
-                            let damp = -k * dt * (value_current - value_previous);
-
-                            my_next[x + y * size] = 2.0 * value_current - value_previous
-                                + coefficient
-                                    * (-4.0 * value_current
-                                        + value_left
-                                        + value_right
-                                        + value_top
-                                        + value_bottom)
-                                + damp;
-                        }
-                    }
-
-                    let start = i * len_per_thread * size;
-                    let end = (i + 1) * len_per_thread * size;
-
-                    let my_start = size;
-                    let my_end = len_per_thread * size + size;
-
-                    // update shared variables
-                    {
-                        // new next to next
-                        let mut next = space_next.lock().unwrap();
-                        let mut next_space = next.space.clone();
-
-                        let mut next_b = next_space.split_off(start);
-                        let mut next_b = next_b.split_off(len_per_thread * size);
-                        next_space.append(&mut my_next[my_start..my_end].to_vec());
-                        next_space.append(&mut next_b);
-                        //assert_eq!(next.space.len(), next_space.len());
-                        next.space = next_space;
-
-                        /*
-                        // old current to current
-                        let mut previous = space_previous.lock().unwrap();
-                        let mut previous_space = previous.space.clone();
-
-                        let mut previous_b = previous_space.split_off(start);
-                        let mut previous_b = previous_b.split_off(len_per_thread * size);
-                        previous_space.append(&mut my_previous[my_start..my_end].to_vec());
-                        previous_space.append(&mut previous_b);
-                        //assert_eq!(current.space.len(), current_space.len());
-                        previous.space = previous_space;
-                        */
-                    }
-
-                    tx_update.send(i).unwrap();
-                }
-            });
-        }
+impl<T: Scalar> WaveSimulator<T> {
+    pub fn new(width: usize, height: usize) -> Result<WaveSimulator<T>, Error> {
+        WaveSimulator::with_settings(Settings::new(width, height))
+    }
 
-        Ok(WaveSimulator {
-            space_previous: space_previous,
-            space_current: space_current,
-            space_next: space_next,
-            space_size: size,
-            tx_update: tx_update_vec,
-            tx_order: tx_order_vec,
+    pub fn with_threads(
+        width: usize,
+        height: usize,
+        num_threads: usize,
+    ) -> Result<WaveSimulator<T>, Error> {
+        WaveSimulator::with_settings(Settings {
+            num_threads: num_threads,
+            ..Settings::new(width, height)
         })
     }
 
+    pub fn with_settings(settings: Settings) -> Result<WaveSimulator<T>, Error> {
+        WaveSimulator::with_space_spec(settings, None)
+    }
+
+    // starting from `space_spec` instead of air, see the builder
+    pub(crate) fn with_space_spec(
+        settings: Settings,
+        space_spec: Option<&[(T, T)]>,
+    ) -> Result<WaveSimulator<T>, Error> {
+        let solver: Box<dyn Solver<T>> = match settings.backend {
+            Backend::Serial => Box::new(SerialSolver::with_space_spec(settings, space_spec)?),
+            Backend::Threaded => Box::new(ThreadedSolver::with_space_spec(settings, space_spec)?),
+            Backend::Rayon => Box::new(RayonSolver::with_space_spec(settings, space_spec)?),
+        };
+
+        Ok(WaveSimulator::with_solver(solver))
+    }
+
+    // drives a solver built by hand
+    pub fn with_solver(solver: Box<dyn Solver<T>>) -> WaveSimulator<T> {
+        let (tx_order, rx_order) = mpsc::channel();
+
+        WaveSimulator {
+            width: solver.grid().width(),
+            height: solver.grid().height(),
+            solver: solver,
+            paused: false,
+            tx_order: tx_order,
+            rx_order: rx_order,
+            scheduled: Vec::new(),
+        }
+    }
+
     pub fn add_gauss(&mut self, x: f32, y: f32, sigma: f32, power: f32) {
-        self.space_current
-            .lock()
-            .unwrap()
-            .add_gauss(x, y, sigma, power);
-        self.space_previous
-            .lock()
-            .unwrap()
-            .add_gauss(x, y, sigma, power);
+        self.grid_mut().add_gauss(x, y, sigma, power);
     }
 
-    pub fn order(&self, order: Order) {
-        self.tx_order.iter().enumerate().for_each(|(i, tx)| {
-            tx.send(order.clone()).unwrap();
-        });
+    // queued until the next call to step() or run()
+    pub fn order(&mut self, order: Order) {
+        let step = self.current_step();
+        self.schedule(step, order);
     }
-}
 
-impl Iterator for WaveSimulator {
-    type Item = Arc<Mutex<Space>>;
-    fn next(&mut self) -> Option<Self::Item> {
-        let (tx_r, rx_r) = mpsc::channel();
+    // applied right before the given step is taken, or at the next call if it already passed
+    pub fn order_at(&mut self, step: usize, order: Order) {
+        self.schedule(step, order);
+    }
 
-        self.tx_update.iter().enumerate().for_each(|(i, tx)| {
-            tx.send((i, tx_r.clone())).unwrap();
-        });
+    pub fn physics(&self) -> Physics {
+        self.grid().physics()
+    }
 
-        let mut count_received = 0;
+    pub fn boundary(&self) -> Boundary {
+        self.grid().boundary()
+    }
 
-        for _ in rx_r {
-            count_received += 1;
-            if count_received >= NUM_THREADS {
-                break;
-            }
-        }
+    pub fn stencil(&self) -> Stencil {
+        self.grid().stencil()
+    }
+
+    pub fn formulation(&self) -> Formulation {
+        self.grid().formulation()
+    }
+
+    // fastest propagation ratio the physics and stencil allow
+    pub fn max_stable_speed(&self) -> f32 {
+        self.grid().max_stable_speed()
+    }
+
+    pub fn current_step(&self) -> usize {
+        self.solver.current_step()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // energy and peak of the field after the last step, none unless it is watched
+    pub fn stats(&self) -> Option<Stats> {
+        self.solver.stats()
+    }
 
-        let space = self.space_current.lock().unwrap().space.clone();
-        self.space_previous.lock().unwrap().space = space;
+    fn grid(&self) -> &Grid<T> {
+        self.solver.grid()
+    }
 
-        let space = self.space_next.lock().unwrap().space.clone();
-        self.space_current.lock().unwrap().space = space;
+    pub(crate) fn grid_mut(&mut self) -> &mut Grid<T> {
+        self.solver.grid_mut()
+    }
 
-        let space = &self.space_next.lock().unwrap().space.clone();
-        for (i, _) in space.as_slice().iter().enumerate() {
-            self.space_next.lock().unwrap().space[i] = 0.0;
+    fn apply(&mut self, order: Order) -> Result<(), Error> {
+        match order {
+            Order::Pause => self.paused = true,
+            Order::Resume => self.paused = false,
+            order => self.solver.apply(order)?,
         }
+        Ok(())
+    }
+
+    // after whatever was sent from a handle before, so orders for the same step keep the
+    // order they were given in
+    fn schedule(&mut self, step: usize, order: Order) {
+        self.receive();
+        self.insert(step, order);
+    }
+
+    // schedules what the handles sent
+    fn receive(&mut self) {
+        let step_count = self.current_step();
+        while let Ok((step, order)) = self.rx_order.try_recv() {
+            self.insert(step.unwrap_or(step_count), order);
+        }
+    }
+
+    fn insert(&mut self, step: usize, order: Order) {
+        let i = self
+            .scheduled
+            .iter()
+            .position(|&(s, _)| s > step)
+            .unwrap_or(self.scheduled.len());
+        self.scheduled.insert(i, (step, order));
+    }
+
+    // stops at the first order that is refused, the ones after it stay scheduled
+    fn apply_due_orders(&mut self) -> Result<(), Error> {
+        self.receive();
 
-        /*
-        let space_size = self.space_size;
-        for y in 1..space_size-1
+        let step_count = self.current_step();
+        while self
+            .scheduled
+            .first()
+            .map_or(false, |&(step, _)| step <= step_count)
         {
-            for x in 1..space_size-1
-            {
-                let value_previous = self.space_previous.get(x, y);
-                let value_current = self.space_current.get(x, y);
-
-                let value_left   = self.space_previous.get(x-1, y);
-                let value_right  = self.space_current.get(x+1, y);
-                let value_top    = self.space_previous.get(x, y-1);
-                let value_bottom = self.space_current.get(x, y+1);
-
-                self.space_previous.put(x, y, value_current);
-                self.space_current.put(x, y, 2.0*value_current - value_previous + coefficient*(-4.0 * value_current + value_left + value_right + value_top + value_bottom));
+            let (_, order) = self.scheduled.remove(0);
+            self.apply(order)?;
+        }
+        Ok(())
+    }
+
+    pub fn handle(&self) -> Handle {
+        Handle {
+            tx_order: self.tx_order.clone(),
+        }
+    }
+
+    pub fn step(&mut self) -> Result<(), Error> {
+        self.run(1)
+    }
+
+    // advances `steps` time steps, only coming back between them to apply scheduled orders.
+    // stops early when paused and nothing resumes it, or with Error::Config at an order over
+    // the cfl limit.
+    // once a worker panicked the field is garbage and every call fails, once it blew up every
+    // call fails until a reset.
+    pub fn run(&mut self, steps: usize) -> Result<(), Error> {
+        // a reset among the due orders clears a blow up, zero steps only reports the failure
+        self.apply_due_orders()?;
+        self.solver.run(0)?;
+
+        // no step is taken while paused, so the first resume is due at this call whatever step
+        // it was ordered at. a pause during the call still stops it.
+        if self.paused && steps > 0 {
+            let resume = self
+                .scheduled
+                .iter()
+                .position(|(_, order)| matches!(order, Order::Resume));
+            if let Some(i) = resume {
+                self.scheduled.remove(i);
+                self.paused = false;
+            }
+        }
+
+        let mut remaining = steps;
+        loop {
+            if remaining == 0 || self.paused {
+                return Ok(());
             }
+
+            // stop at the next scheduled order so it is applied exactly at its step
+            let steps = match self.scheduled.first() {
+                Some(&(step, _)) => remaining.min(step - self.current_step()),
+                None => remaining,
+            };
+            // the steps are checked once the solver is back, a blow up runs on for at most
+            // a window
+            let steps = match self.grid().watch() {
+                Some(watch) => steps.min(watch.window),
+                None => steps,
+            };
+            self.solver.run(steps)?;
+            remaining -= steps;
+            self.apply_due_orders()?;
+        }
+    }
+
+    // stops the solver, reporting the first worker that panicked
+    pub fn shutdown(self) -> Result<(), Error> {
+        self.solver.shutdown()
+    }
+
+    pub fn field(&self) -> &[T] {
+        self.solver.field()
+    }
+
+    pub fn field_mut(&mut self) -> &mut [T] {
+        self.grid_mut().field_mut()
+    }
+
+    pub(crate) fn nudge(&mut self, cell: usize, by: T) {
+        self.grid_mut().nudge(cell, by);
+    }
+
+    // Order::Reset right away rather than before the next step
+    pub(crate) fn reset(&mut self) -> Result<(), Error> {
+        self.solver.apply(Order::Reset)
+    }
+
+    pub fn space_spec(&self) -> &[(T, T)] {
+        self.grid().space_spec()
+    }
+
+    pub fn space_spec_mut(&mut self) -> &mut [(T, T)] {
+        self.grid_mut().space_spec_mut()
+    }
+
+    pub fn density(&self) -> &[T] {
+        self.grid().density()
+    }
+
+    pub fn density_mut(&mut self) -> &mut [T] {
+        self.grid_mut().density_mut()
+    }
+
+    // x velocity on the left face of each cell and y velocity on the top face, rows of
+    // width + 1 and height + 1 rows of width, so the right and bottom edge are included.
+    // only the staggered formulation has them.
+    pub fn velocity(&self) -> Option<(&[T], &[T])> {
+        self.grid().velocity()
+    }
+
+    // velocity at the centre of a cell, the mean of its faces
+    pub fn velocity_at(&self, x: usize, y: usize) -> Option<(T, T)> {
+        let (velocity_x, velocity_y) = self.velocity()?;
+        let face = x + y * (self.width + 1);
+        let i = x + y * self.width;
+
+        Some((
+            (velocity_x[face] + velocity_x[face + 1]) / T::splat(2.0),
+            (velocity_y[i] + velocity_y[i + self.width]) / T::splat(2.0),
+        ))
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> T {
+        self.field()[x + y * self.width]
+    }
+
+    // copy of the current field, spec and density
+    pub fn snapshot(&self) -> Space<T> {
+        Space {
+            space: self.field().to_vec(),
+            space_spec: self.space_spec().to_vec(),
+            space_density: self.density().to_vec(),
+            width: self.width,
+            height: self.height,
         }
-        */
+    }
+}
+
+impl Handle {
+    // fails with Disconnected once the simulator is gone
+    pub fn order(&self, order: Order) -> Result<(), Error> {
+        Ok(self.tx_order.send((None, order))?)
+    }
 
-        Some(self.space_current.clone())
+    pub fn order_at(&self, step: usize, order: Order) -> Result<(), Error> {
+        Ok(self.tx_order.send((Some(step), order))?)
     }
 }
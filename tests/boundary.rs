@@ -0,0 +1,128 @@
+use audio_sim::wave_simulator::*;
+
+const SIZE: usize = 64;
+
+// sum of squares of the field, relative to right after the pulse started
+fn residual_energy(boundary: Boundary, steps: usize) -> f32 {
+    let mut wave_simulator = WaveSimulator::with_settings(Settings {
+        physics: Physics::audio(0.02, 44100),
+        boundary: boundary,
+        ..Settings::new(SIZE, SIZE)
+    })
+    .unwrap();
+
+    wave_simulator.order(Order::FillMaterial(
+        Region::Rect {
+            x: 0,
+            y: 0,
+            width: SIZE,
+            height: SIZE,
+        },
+        Material::air(20.0),
+    ));
+    let centre = SIZE as f32 / 2.0;
+    wave_simulator.add_gauss(centre, centre, 3.0, 1.0);
+
+    let energy = |w: &WaveSimulator| w.field().iter().map(|v| v * v).sum::<f32>();
+    wave_simulator.run(10).unwrap();
+    let initial = energy(&wave_simulator);
+    wave_simulator.run(steps).unwrap();
+
+    energy(&wave_simulator) / initial
+}
+
+#[test]
+fn pressure_release_edges_keep_the_pulse() {
+    assert!(residual_energy(Boundary::all(Edge::Dirichlet), 400) > 0.5);
+}
+
+#[test]
+fn mur_edges_absorb_the_pulse() {
+    assert!(residual_energy(Boundary::all(Edge::Mur1), 400) < 0.01);
+    assert!(residual_energy(Boundary::all(Edge::Mur2), 400) < 0.01);
+}
+
+#[test]
+fn pml_absorbs_the_pulse() {
+    assert!(residual_energy(Boundary::all(Edge::Pml { thickness: 12 }), 400) < 0.001);
+}
+
+#[test]
+fn edges_are_independent() {
+    let boundary = Boundary {
+        left: Edge::Pml { thickness: 12 },
+        right: Edge::Mur2,
+        ..Boundary::all(Edge::Neumann)
+    };
+    let residual = residual_energy(boundary, 400);
+    assert!(residual > 0.01 && residual < 0.5);
+}
+
+#[test]
+fn layers_must_fit_the_grid() {
+    let settings = Settings {
+        boundary: Boundary::all(Edge::Pml {
+            thickness: SIZE / 2 + 1,
+        }),
+        ..Settings::new(SIZE, SIZE)
+    };
+    assert!(WaveSimulator::<f32>::with_settings(settings).is_err());
+}
+
+fn simulator(boundary: Boundary) -> WaveSimulator {
+    WaveSimulator::with_settings(Settings {
+        boundary: boundary,
+        ..Settings::new(SIZE, SIZE)
+    })
+    .unwrap()
+}
+
+#[test]
+fn rigid_edges_keep_the_mean_pressure() {
+    let mut wave_simulator = simulator(Boundary::all(Edge::Neumann));
+    wave_simulator.add_gauss(5.0, 8.0, 2.0, 1.0);
+
+    let sum = |w: &WaveSimulator| w.field().iter().sum::<f32>();
+    let initial = sum(&wave_simulator);
+    wave_simulator.run(300).unwrap();
+    assert!((sum(&wave_simulator) - initial).abs() < 1e-3 * initial);
+}
+
+#[test]
+fn periodic_edges_wrap_around() {
+    // the same impulse, once in the middle and once shifted across the corner
+    let shift = (40, 50);
+    let mut a = simulator(Boundary::all(Edge::Periodic));
+    let mut b = simulator(Boundary::all(Edge::Periodic));
+    a.order(Order::Impulse(Impulse::Point {
+        x: 20.0,
+        y: 10.0,
+        power: 1.0,
+    }));
+    b.order(Order::Impulse(Impulse::Point {
+        x: ((20 + shift.0) % SIZE) as f32,
+        y: ((10 + shift.1) % SIZE) as f32,
+        power: 1.0,
+    }));
+    a.run(200).unwrap();
+    b.run(200).unwrap();
+
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let expected = a.get((x + SIZE - shift.0) % SIZE, (y + SIZE - shift.1) % SIZE);
+            assert!((b.get(x, y) - expected).abs() < 1e-5);
+        }
+    }
+}
+
+#[test]
+fn periodic_edges_come_in_pairs() {
+    let settings = Settings {
+        boundary: Boundary {
+            left: Edge::Periodic,
+            ..Boundary::default()
+        },
+        ..Settings::new(SIZE, SIZE)
+    };
+    assert!(WaveSimulator::<f32>::with_settings(settings).is_err());
+}
@@ -0,0 +1,143 @@
+use audio_sim::wave_simulator::*;
+
+const WIDTH: usize = 24;
+const HEIGHT: usize = 18;
+
+fn config_error<T: Scalar>(builder: &WaveSimulatorBuilder<T>) -> String {
+    match builder.build() {
+        Err(Error::Config(reason)) => reason,
+        other => panic!("{:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn builds_what_the_settings_would() {
+    let boundary = Boundary::all(Edge::Pml { thickness: 4 });
+    let mut built = WaveSimulator::builder()
+        .size(WIDTH, HEIGHT)
+        .threads(2)
+        .dx(0.02)
+        .dt(1.0 / 44100.0)
+        .boundary(boundary)
+        .stencil(Stencil::FourthOrder)
+        .build()
+        .unwrap();
+    let mut set = WaveSimulator::<f32>::with_settings(Settings {
+        num_threads: 2,
+        physics: Physics {
+            dx: 0.02,
+            dt: 1.0 / 44100.0,
+            ..Physics::default()
+        },
+        boundary: boundary,
+        stencil: Stencil::FourthOrder,
+        ..Settings::new(WIDTH, HEIGHT)
+    })
+    .unwrap();
+
+    assert_eq!(built.physics(), set.physics());
+    assert_eq!(built.boundary(), set.boundary());
+    assert_eq!(built.stencil(), set.stencil());
+
+    for wave_simulator in [&mut built, &mut set].iter_mut() {
+        wave_simulator.add_gauss(10.0, 9.0, 2.0, 1.0);
+        wave_simulator.run(40).unwrap();
+    }
+    assert_eq!(built.field(), set.field());
+}
+
+#[test]
+fn every_setting_can_be_built() {
+    let builder = WaveSimulator::builder()
+        .size(WIDTH, HEIGHT)
+        .sample_rate(48000)
+        .variable_density(true)
+        .backend(Backend::Threaded)
+        .simd(false)
+        .tiling(Some(Tiling { steps: 4, rows: 6 }))
+        .watch(Some(Watch::default()));
+    let settings = builder.settings().unwrap();
+
+    assert_eq!(settings.physics.sample_rate, 48000);
+    assert!(settings.variable_density);
+    assert_eq!(settings.backend, Backend::Threaded);
+    assert!(!settings.simd);
+    assert_eq!(settings.tiling, Some(Tiling { steps: 4, rows: 6 }));
+    assert!(settings.watch.is_some());
+    builder.build().unwrap();
+
+    let settings = builder.tiling(None).watch(None).settings().unwrap();
+    assert_eq!(settings.tiling, None);
+    assert!(settings.watch.is_none());
+}
+
+#[test]
+fn initial_spec_and_field_are_used() {
+    // values an f32 can't hold
+    let space_spec: Vec<(f64, f64)> = (0..WIDTH * HEIGHT)
+        .map(|i| (0.1 + (i % 7) as f64 * 0.01, (i % 3) as f64 * 0.1))
+        .collect();
+    let field: Vec<f64> = (0..WIDTH * HEIGHT).map(|i| (i % 5) as f64 * 0.1).collect();
+
+    let wave_simulator = WaveSimulator::builder()
+        .size(WIDTH, HEIGHT)
+        .precision::<f64>()
+        .space_spec(space_spec.clone())
+        .field(field.clone())
+        .build()
+        .unwrap();
+
+    let snapshot: Space<f64> = wave_simulator.snapshot();
+    assert_eq!(snapshot.space, field);
+    assert_eq!(snapshot.space_spec, space_spec);
+}
+
+#[test]
+fn initial_fields_start_at_rest() {
+    let mut field = vec![0.0; WIDTH * HEIGHT];
+    field[10 + 8 * WIDTH] = 1.0;
+
+    let mut built = WaveSimulator::builder()
+        .size(WIDTH, HEIGHT)
+        .field(field)
+        .build()
+        .unwrap();
+    let mut ordered = WaveSimulator::builder()
+        .size(WIDTH, HEIGHT)
+        .build()
+        .unwrap();
+    ordered.order(Order::Impulse(Impulse::Point {
+        x: 10.0,
+        y: 8.0,
+        power: 1.0,
+    }));
+
+    built.run(20).unwrap();
+    ordered.run(20).unwrap();
+    assert_eq!(built.field(), ordered.field());
+}
+
+#[test]
+fn everything_is_checked_on_build() {
+    let builder = WaveSimulator::builder().size(WIDTH, HEIGHT);
+    let cells = WIDTH * HEIGHT;
+
+    assert!(config_error(&WaveSimulator::builder()).contains("size"));
+    assert!(config_error(&builder.threads(0)).contains("threads"));
+    assert!(config_error(&builder.dt(0.0)).contains("dt"));
+    assert!(
+        config_error(&builder.boundary(Boundary::all(Edge::Pml { thickness: 10 })))
+            .contains("edges")
+    );
+    assert!(config_error(&builder.space_spec(vec![(0.1, 0.0); cells - 1])).contains("spec"));
+    assert!(config_error(&builder.field(vec![0.0; cells + 1])).contains("field"));
+
+    let mut field = vec![0.0; cells];
+    field[3] = std::f32::NAN;
+    assert!(config_error(&builder.field(field)).contains("finite"));
+
+    let mut space_spec = vec![(0.1, 0.0); cells];
+    space_spec[5 + 2 * WIDTH].0 = 1000.0;
+    let reason = config_error(&builder.precision::<f64>().space_spec(space_spec));
+    assert!(reason.contains("(5, 2)"), "{}", reason);
+}
@@ -0,0 +1,121 @@
+use audio_sim::wave_simulator::*;
+
+// odd so rows end in a partial set of lanes and bands and tiles don't split evenly
+pub const WIDTH: usize = 37;
+pub const HEIGHT: usize = 45;
+
+// a pulse in air next to a denser block, with orders landing in the middle of the run.
+// the field and, on a staggered grid, the velocities.
+pub fn run<T: Scalar>(settings: Settings) -> Vec<T> {
+    let mut wave_simulator = WaveSimulator::<T>::with_settings(Settings {
+        physics: Physics::audio(0.02, 44100),
+        ..settings
+    })
+    .unwrap();
+
+    let all = Region::Rect {
+        x: 0,
+        y: 0,
+        width: WIDTH,
+        height: HEIGHT,
+    };
+    let block = Region::Rect {
+        x: 22,
+        y: 6,
+        width: 9,
+        height: 25,
+    };
+    wave_simulator.order(Order::FillMaterial(all, Material::air(20.0)));
+    wave_simulator.order(Order::FillMaterial(block, Material::new(480.0, 2.5, 0.3)));
+    wave_simulator.add_gauss(13.0, 21.0, 2.0, 1.0);
+    wave_simulator.order_at(
+        50,
+        Order::Impulse(Impulse::Point {
+            x: 8.5,
+            y: 35.25,
+            power: 0.5,
+        }),
+    );
+    wave_simulator.order_at(
+        70,
+        Order::Fill(
+            Region::Circle {
+                x: 28.0,
+                y: 38.0,
+                radius: 4.0,
+            },
+            (200.0, 0.05),
+        ),
+    );
+
+    // runs that don't end on a tile
+    wave_simulator.run(23).unwrap();
+    wave_simulator.run(127).unwrap();
+
+    let mut result = wave_simulator.field().to_vec();
+    if let Some((velocity_x, velocity_y)) = wave_simulator.velocity() {
+        result.extend_from_slice(velocity_x);
+        result.extend_from_slice(velocity_y);
+    }
+    wave_simulator.shutdown().unwrap();
+    result
+}
+
+// every edge, stencil and formulation the paths have to get right
+pub fn scenes() -> Vec<Settings> {
+    let settings = Settings {
+        num_threads: 3,
+        ..Settings::new(WIDTH, HEIGHT)
+    };
+    let mixed = Boundary {
+        left: Edge::Pml { thickness: 6 },
+        right: Edge::Mur2,
+        top: Edge::Neumann,
+        bottom: Edge::Mur1,
+    };
+
+    vec![
+        settings,
+        Settings {
+            boundary: mixed,
+            ..settings
+        },
+        Settings {
+            boundary: mixed,
+            stencil: Stencil::NinePoint,
+            ..settings
+        },
+        Settings {
+            boundary: Boundary::all(Edge::Pml { thickness: 6 }),
+            stencil: Stencil::FourthOrder,
+            ..settings
+        },
+        Settings {
+            boundary: mixed,
+            variable_density: true,
+            ..settings
+        },
+        Settings {
+            boundary: Boundary {
+                left: Edge::Periodic,
+                right: Edge::Periodic,
+                ..Boundary::all(Edge::Mur2)
+            },
+            variable_density: true,
+            ..settings
+        },
+        Settings {
+            boundary: Boundary {
+                top: Edge::Periodic,
+                bottom: Edge::Periodic,
+                ..Boundary::default()
+            },
+            ..settings
+        },
+        Settings {
+            boundary: Boundary::all(Edge::Neumann),
+            formulation: Formulation::Staggered,
+            ..settings
+        },
+    ]
+}
@@ -0,0 +1,244 @@
+use audio_sim::wave_simulator::*;
+
+const LENGTH: usize = 40;
+const DX: f32 = 0.01;
+const DT: f32 = 1.0 / 44100.0;
+const STIFFNESS: f32 = 1e8;
+
+// 1 cm samples at 44.1 kHz, plucked near the left end
+fn string() -> WaveSimulator1D<f64> {
+    let mut string = WaveSimulator1D::with_settings(Settings1D {
+        physics: Physics::audio(DX, 44100),
+        ..Settings1D::new(LENGTH)
+    })
+    .unwrap();
+    string.set_tension(100.0, 0.01);
+    string.pluck(10.0, 0.001);
+    string
+}
+
+fn plate() -> WaveSimulator<f64> {
+    WaveSimulator::builder()
+        .size(20, 20)
+        .dx(DX)
+        .dt(DT)
+        .formulation(Formulation::Plate { loss: 0.0 })
+        .space_spec(vec![(1.0, 0.0); 20 * 20])
+        .precision::<f64>()
+        .build()
+        .unwrap()
+}
+
+fn peak(field: &[f64]) -> f64 {
+    field.iter().fold(0.0, |peak, v| v.abs().max(peak))
+}
+
+#[test]
+fn a_string_drives_a_plate() {
+    let mut instrument = Instrument::new();
+    let string_id = instrument.add(string()).unwrap();
+    let plate_id = instrument.add(plate()).unwrap();
+    instrument
+        .couple(Coupling {
+            from: (string_id, Contact::Point([30, 0, 0])),
+            to: (plate_id, Contact::Point([10, 10, 0])),
+            stiffness: STIFFNESS,
+            reaction: 0.0,
+        })
+        .unwrap();
+
+    let mut alone = string();
+    instrument.run(2000).unwrap();
+    alone.run(2000).unwrap();
+
+    // one way, the string doesn't feel the plate
+    let string = instrument
+        .domain::<WaveSimulator1D<f64>>(string_id)
+        .unwrap();
+    assert_eq!(string.field(), alone.field());
+    let plate = instrument.domain::<WaveSimulator<f64>>(plate_id).unwrap();
+    assert!(peak(plate.field()) > 1e-6, "{}", peak(plate.field()));
+    assert_eq!(instrument.current_step(), 2000);
+
+    assert!(instrument.domain::<WaveSimulator<f32>>(plate_id).is_none());
+    assert!(instrument.domain::<WaveSimulator<f64>>(2).is_none());
+}
+
+#[test]
+fn a_reaction_takes_from_the_string() {
+    let run = |reaction: f32| {
+        let mut instrument = Instrument::new();
+        let string_id = instrument.add(string()).unwrap();
+        let plate_id = instrument.add(plate()).unwrap();
+        instrument
+            .couple(Coupling {
+                from: (string_id, Contact::Point([30, 0, 0])),
+                to: (plate_id, Contact::Point([10, 10, 0])),
+                stiffness: STIFFNESS,
+                reaction: reaction,
+            })
+            .unwrap();
+        instrument.run(2000).unwrap();
+        let string = instrument
+            .domain::<WaveSimulator1D<f64>>(string_id)
+            .unwrap();
+        string.field().to_vec()
+    };
+
+    let (free, loaded) = (run(0.0), run(1.0));
+    assert!(loaded.iter().all(|v| v.is_finite()));
+    assert_ne!(free, loaded);
+}
+
+#[test]
+fn lines_pair_their_cells() {
+    // a silent strip of air under the string, driven along a row the other way round.
+    // 2 cm cells hold air at 44.1 kHz.
+    let air = WaveSimulator::<f64>::with_settings(Settings {
+        physics: Physics::audio(2.0 * DX, 44100),
+        ..Settings::new(2 * LENGTH, 3)
+    })
+    .unwrap();
+    let mut instrument = Instrument::new();
+    let string_id = instrument.add(string()).unwrap();
+    let air_id = instrument.add(air).unwrap();
+    instrument
+        .couple(Coupling {
+            from: (string_id, Contact::Line([0, 0, 0], [LENGTH - 1, 0, 0])),
+            to: (air_id, Contact::Line([2 * LENGTH - 1, 1, 0], [0, 1, 0])),
+            stiffness: STIFFNESS,
+            reaction: 0.0,
+        })
+        .unwrap();
+
+    let before = string().field().to_vec();
+    instrument.step().unwrap();
+
+    // the string is stretched over twice its cells
+    let air = instrument.domain::<WaveSimulator<f64>>(air_id).unwrap();
+    let by = STIFFNESS as f64 * DT as f64 * DT as f64;
+    for x in 0..2 * LENGTH {
+        let expected = by * before[(2 * LENGTH - 1 - x) / 2];
+        assert!((air.get(x, 1) - expected).abs() < 1e-12, "{}", x);
+        assert!(air.get(x, 0).abs() < 1e-12, "{}", x);
+    }
+}
+
+#[test]
+fn couplings_check_their_contacts() {
+    let mut instrument = Instrument::new();
+    let string_id = instrument.add(string()).unwrap();
+    let plate_id = instrument.add(plate()).unwrap();
+    let coupling = Coupling {
+        from: (string_id, Contact::Point([30, 0, 0])),
+        to: (plate_id, Contact::Point([10, 10, 0])),
+        stiffness: STIFFNESS,
+        reaction: 1.0,
+    };
+    let config_error = |result: Result<(), Error>| match result {
+        Err(Error::Config(reason)) => reason,
+        other => panic!("{:?}", other),
+    };
+
+    config_error(instrument.couple(Coupling {
+        to: (plate_id, Contact::Line([0, 0, 0], [20, 0, 0])),
+        ..coupling
+    }));
+    config_error(instrument.couple(Coupling {
+        from: (string_id, Contact::Point([0, 1, 0])),
+        ..coupling
+    }));
+    config_error(instrument.couple(Coupling {
+        to: (2, Contact::Point([0, 0, 0])),
+        ..coupling
+    }));
+    config_error(instrument.couple(Coupling {
+        stiffness: 1.0 / (DT * DT),
+        ..coupling
+    }));
+    config_error(instrument.couple(Coupling {
+        reaction: -1.0,
+        ..coupling
+    }));
+    instrument.couple(coupling).unwrap();
+
+    let slower = WaveSimulator::<f32>::with_settings(Settings {
+        physics: Physics::audio(4.0 * DX, 22050),
+        ..Settings::new(4, 4)
+    })
+    .unwrap();
+    let other_rate = WaveSimulator::<f32>::with_settings(Settings {
+        physics: Physics {
+            sample_rate: 48000,
+            ..Physics::audio(2.0 * DX, 44100)
+        },
+        ..Settings::new(4, 4)
+    })
+    .unwrap();
+    let add_error = |result: Result<usize, Error>| match result {
+        Err(Error::Config(reason)) => reason,
+        other => panic!("{:?}", other),
+    };
+    assert!(add_error(instrument.add(slower)).contains("dt"));
+    assert!(add_error(instrument.add(other_rate)).contains("sample rate"));
+}
+
+#[test]
+fn pushes_leave_the_watch_on() {
+    // a patch of air over the limit blows up while the string keeps pushing on it
+    let mut air = WaveSimulator::<f32>::with_settings(Settings {
+        physics: Physics::audio(2.0 * DX, 44100),
+        ..Settings::new(40, 30)
+    })
+    .unwrap();
+    let max = air.max_stable_speed();
+    for (i, cell) in air.space_spec_mut().iter_mut().enumerate() {
+        let (x, y) = (i % 40, i / 40);
+        let speed = if x >= 25 && x < 31 && y >= 10 && y < 16 {
+            max * 1.05
+        } else {
+            max / 2.0
+        };
+        *cell = (speed, 0.0);
+    }
+    air.add_gauss(12.0, 15.0, 2.0, 1.0);
+
+    let mut instrument = Instrument::new();
+    let string_id = instrument.add(string()).unwrap();
+    let air_id = instrument.add(air).unwrap();
+    instrument
+        .couple(Coupling {
+            from: (string_id, Contact::Point([30, 0, 0])),
+            to: (air_id, Contact::Point([5, 5, 0])),
+            stiffness: STIFFNESS,
+            reaction: 0.0,
+        })
+        .unwrap();
+
+    match instrument.run(2000) {
+        Err(Error::Unstable(Instability {
+            cause: Cause::Growth(_),
+            ..
+        })) => (),
+        other => panic!("{:?}", other),
+    }
+
+    // the string took a step the air didn't, nothing steps until they are put back together
+    let steps = instrument.current_step();
+    assert!(matches!(instrument.step(), Err(Error::OutOfStep(1))));
+    assert!(matches!(instrument.run(10), Err(Error::OutOfStep(1))));
+    assert_eq!(instrument.current_step(), steps);
+
+    let air = instrument.domain_mut::<WaveSimulator<f32>>(air_id).unwrap();
+    for cell in air.space_spec_mut().iter_mut() {
+        cell.0 = max / 2.0;
+    }
+    instrument.reset().unwrap();
+    let string = instrument
+        .domain::<WaveSimulator1D<f64>>(string_id)
+        .unwrap();
+    assert!(string.field().iter().all(|v| *v == 0.0));
+    let air = instrument.domain::<WaveSimulator<f32>>(air_id).unwrap();
+    assert!(air.field().iter().all(|v| *v == 0.0));
+    instrument.run(100).unwrap();
+}
@@ -0,0 +1,110 @@
+use audio_sim::wave_simulator::*;
+
+const WIDTH: usize = 600;
+const INTERFACE: usize = 300;
+const PROBE: usize = 250;
+
+// reflected over incident pressure at a plane interface between air and `material`,
+// measured in a one cell high periodic channel, so the waves are plane
+fn reflection(material: Material, variable_density: bool) -> f32 {
+    let mut wave_simulator = WaveSimulator::<f32>::with_settings(Settings {
+        physics: Physics::audio(0.02, 44100),
+        boundary: Boundary {
+            left: Edge::Pml { thickness: 40 },
+            right: Edge::Pml { thickness: 40 },
+            top: Edge::Periodic,
+            bottom: Edge::Periodic,
+        },
+        variable_density: variable_density,
+        ..Settings::new(WIDTH, 1)
+    })
+    .unwrap();
+
+    let rect = |x: usize, width: usize| Region::Rect {
+        x: x,
+        y: 0,
+        width: width,
+        height: 1,
+    };
+    wave_simulator.order(Order::FillMaterial(rect(0, INTERFACE), Material::air(20.0)));
+    wave_simulator.order(Order::FillMaterial(
+        rect(INTERFACE, WIDTH - INTERFACE),
+        material,
+    ));
+    // splits in two, half of it heads for the interface
+    wave_simulator.add_gauss(200.0, 0.0, 6.0, 1.0);
+
+    let mut incident = 0.0f32;
+    let mut reflected = 0.0f32;
+    for step in 0..520 {
+        wave_simulator.step().unwrap();
+        let value = wave_simulator.get(PROBE, 0);
+        // the incident pulse passes the probe around step 130, the reflection around 390
+        if step < 260 {
+            incident = if value.abs() > incident.abs() {
+                value
+            } else {
+                incident
+            };
+        } else {
+            reflected = if value.abs() > reflected.abs() {
+                value
+            } else {
+                reflected
+            };
+        }
+    }
+
+    reflected / incident
+}
+
+fn expected(material: Material) -> f32 {
+    let air = Material::air(20.0).impedance();
+    (material.impedance() - air) / (material.impedance() + air)
+}
+
+#[test]
+fn denser_material_reflects_by_impedance() {
+    let material = Material::new(Material::air(20.0).speed, 4.8, 0.0);
+    assert!((reflection(material, true) - expected(material)).abs() < 0.02);
+}
+
+#[test]
+fn faster_material_reflects_by_impedance() {
+    let material = Material::new(600.0, 3.0, 0.0);
+    assert!((reflection(material, true) - expected(material)).abs() < 0.02);
+}
+
+#[test]
+fn lighter_material_reflects_inverted() {
+    let material = Material::new(Material::air(20.0).speed, 0.3, 0.0);
+    let reflection = reflection(material, true);
+    assert!(reflection < 0.0);
+    assert!((reflection - expected(material)).abs() < 0.02);
+}
+
+#[test]
+fn walls_reflect_what_they_dont_absorb() {
+    let reflection = reflection(Material::wall(0.5), true);
+    assert!(
+        (reflection * reflection - 0.5).abs() < 0.03,
+        "{}",
+        reflection
+    );
+}
+
+#[test]
+fn constant_density_ignores_density() {
+    let material = Material::new(Material::air(20.0).speed, 4.8, 0.0);
+    assert!(reflection(material, false).abs() < 0.01);
+}
+
+#[test]
+fn variable_density_needs_the_five_point_stencil() {
+    let settings = Settings {
+        variable_density: true,
+        stencil: Stencil::NinePoint,
+        ..Settings::new(8, 8)
+    };
+    assert!(WaveSimulator::<f32>::with_settings(settings).is_err());
+}
@@ -0,0 +1,93 @@
+use audio_sim::oscillator::Oscillator;
+use audio_sim::wave_simulator::*;
+
+#[test]
+fn invalid_settings_say_what_is_wrong() {
+    let settings = Settings {
+        stencil: Stencil::NinePoint,
+        variable_density: true,
+        ..Settings::new(16, 16)
+    };
+    match WaveSimulator::<f32>::with_settings(settings) {
+        Err(Error::Config(reason)) => assert!(reason.contains("density"), "{}", reason),
+        other => panic!("{:?}", other.map(|_| ())),
+    }
+
+    let settings = Settings {
+        num_threads: 0,
+        ..Settings::new(16, 16)
+    };
+    assert!(matches!(
+        ThreadedSolver::<f32>::new(settings),
+        Err(Error::Config(_))
+    ));
+}
+
+#[test]
+fn orders_over_the_cfl_limit_are_refused() {
+    // one step is one sample unless asked otherwise
+    assert!((Physics::default().samples_per_step() - 1.0).abs() < 1e-6);
+
+    let mut wave_simulator = WaveSimulator::<f32>::new(16, 16).unwrap();
+    let speed = wave_simulator.max_stable_speed() * 2.0;
+    wave_simulator.order(Order::Change(Parameter::PropagationRatio(3, 4, speed)));
+    wave_simulator.order(Order::Pause);
+
+    match wave_simulator.run(10) {
+        Err(Error::Config(reason)) => assert!(reason.contains("cfl"), "{}", reason),
+        other => panic!("{:?}", other),
+    }
+    assert!(wave_simulator.space_spec()[3 + 4 * 16].0 < speed);
+
+    // the orders after it are still applied
+    wave_simulator.run(10).unwrap();
+    assert!(wave_simulator.is_paused());
+    assert_eq!(wave_simulator.current_step(), 0);
+}
+
+#[test]
+fn handles_notice_the_simulator_is_gone() {
+    let wave_simulator = WaveSimulator::<f32>::new(16, 16).unwrap();
+    let handle = wave_simulator.handle();
+    handle.order(Order::Pause).unwrap();
+
+    wave_simulator.shutdown().unwrap();
+    assert!(matches!(
+        handle.order(Order::Resume),
+        Err(Error::Disconnected)
+    ));
+    assert!(matches!(
+        handle.order_at(10, Order::Reset),
+        Err(Error::Disconnected)
+    ));
+}
+
+#[test]
+fn orders_keep_the_order_they_were_given_in() {
+    let mut wave_simulator = WaveSimulator::<f32>::new(16, 16).unwrap();
+    let handle = wave_simulator.handle();
+
+    // the handle's pause was given first, so the simulator's own resume wins
+    handle.order(Order::Pause).unwrap();
+    wave_simulator.order(Order::Resume);
+    wave_simulator.run(3).unwrap();
+    assert!(!wave_simulator.is_paused());
+    assert_eq!(wave_simulator.current_step(), 3);
+
+    // a reset after the pause still lands, nothing runs after either
+    wave_simulator.add_gauss(8.0, 8.0, 1.0, 1.0);
+    wave_simulator.order_at(5, Order::Pause);
+    handle.order_at(5, Order::Reset).unwrap();
+    wave_simulator.run(10).unwrap();
+    assert!(wave_simulator.is_paused());
+    assert_eq!(wave_simulator.current_step(), 5);
+    assert!(wave_simulator.field().iter().all(|&v| v == 0.0));
+}
+
+#[test]
+fn oscillators_need_every_field() {
+    let builder = Oscillator::new().frequency(440.0).sampling_rate(44100);
+    assert!(matches!(builder.build(), Err(Error::Config(_))));
+    assert!(builder.index(0).build().is_ok());
+    assert!(builder.index(0).sampling_rate(0).build().is_err());
+}
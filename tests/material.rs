@@ -0,0 +1,87 @@
+use audio_sim::wave_simulator::*;
+
+#[test]
+fn air_and_water_match_the_tables() {
+    let air = Material::air(20.0);
+    assert!((air.speed - 343.2).abs() < 0.5, "{}", air.speed);
+    assert!((air.density - 1.204).abs() < 0.005, "{}", air.density);
+    assert!((air.impedance() - 413.0).abs() < 2.0, "{}", air.impedance());
+    assert!((Material::water(20.0).speed - 1494.4).abs() < 0.1);
+}
+
+#[test]
+fn walls_absorb_their_coefficient() {
+    let air = Material::air(20.0);
+    for &absorption in [0.02, 0.1, 0.5, 0.9].iter() {
+        let wall = Material::wall(absorption);
+        let absorbed = wall.absorption(&air);
+        assert!(
+            (absorbed - absorption).abs() < 1e-4,
+            "{} {}",
+            absorbed,
+            absorption
+        );
+        // harder than the air, not softer
+        assert!(wall.impedance() > air.impedance());
+    }
+    // the same material either way
+    assert!((air.absorption(&Material::wall(0.3)) - 0.3).abs() < 1e-4);
+}
+
+#[test]
+fn concrete_fits_audio_rates() {
+    // anywhere the air runs
+    let (air, concrete) = (Material::air(20.0), Material::concrete(0.02));
+    let fits = |material: Material, dx: f32| {
+        WaveSimulator::<f32>::builder()
+            .size(8, 8)
+            .dx(dx)
+            .space_spec(vec![material.spec(); 8 * 8])
+            .build()
+            .is_ok()
+    };
+    for &dx in [0.005, 0.01, 0.02, 0.1].iter() {
+        assert_eq!(fits(air, dx), fits(concrete, dx), "{}", dx);
+    }
+    assert!(fits(concrete, 0.02));
+}
+
+#[test]
+fn default_cells_are_air() {
+    // a pulse crosses 6 cells of 2 cm in 20 samples at 343 m/s
+    let mut plane = WaveSimulator::<f32>::new(40, 40).unwrap();
+    let mut room = WaveSimulator3D::<f32>::new(24, 24, 24).unwrap();
+    assert_eq!(plane.space_spec()[0], Material::air(20.0).spec());
+    assert_eq!(room.space_spec()[0], Material::air(20.0).spec());
+
+    plane.add_gauss(20.0, 20.0, 1.0, 1.0);
+    room.add_gauss(12.0, 12.0, 12.0, 1.0, 1.0);
+    let (plane_peak, room_peak) = (plane.get(20, 20), room.get(12, 12, 12));
+    plane.run(20).unwrap();
+    room.run(20).unwrap();
+
+    assert!(
+        plane.get(26, 20).abs() > plane_peak * 1e-2,
+        "{}",
+        plane.get(26, 20)
+    );
+    assert!(
+        room.get(18, 12, 12).abs() > room_peak * 1e-2,
+        "{}",
+        room.get(18, 12, 12)
+    );
+}
+
+#[test]
+fn specs_go_both_ways() {
+    let materials = [
+        Material::air(20.0),
+        Material::water(10.0),
+        Material::wall(0.3),
+        Material::new(480.0, 2.5, 30.0),
+    ];
+    for material in materials.iter() {
+        let spec = material.spec();
+        assert_eq!(Material::from_spec(spec, material.density), *material);
+    }
+}
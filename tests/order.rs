@@ -0,0 +1,31 @@
+use audio_sim::wave_simulator::*;
+
+#[test]
+fn paused_simulators_take_a_later_resume() {
+    let mut wave_simulator = WaveSimulator::<f32>::new(16, 16).unwrap();
+    wave_simulator.order_at(2, Order::Pause);
+    wave_simulator.order_at(6, Order::Resume);
+    wave_simulator.order_at(8, Order::Pause);
+
+    wave_simulator.run(5).unwrap();
+    assert!(wave_simulator.is_paused());
+    assert_eq!(wave_simulator.current_step(), 2);
+
+    // step 6 never comes while paused, the resume is taken anyway and the pause after it
+    // still waits for its step
+    wave_simulator.run(10).unwrap();
+    assert!(wave_simulator.is_paused());
+    assert_eq!(wave_simulator.current_step(), 8);
+
+    // nothing left to resume it
+    wave_simulator.run(10).unwrap();
+    assert_eq!(wave_simulator.current_step(), 8);
+
+    wave_simulator
+        .handle()
+        .order_at(100, Order::Resume)
+        .unwrap();
+    wave_simulator.run(3).unwrap();
+    assert!(!wave_simulator.is_paused());
+    assert_eq!(wave_simulator.current_step(), 11);
+}
@@ -0,0 +1,153 @@
+use std::f64::consts::PI;
+
+use audio_sim::wave_simulator::*;
+
+// 1 cm cells at 44.1 kHz, the cells just outside the plate hold it
+const SIZE: usize = 15;
+const DX: f32 = 0.01;
+const DT: f32 = 1.0 / 44100.0;
+const STIFFNESS: f32 = 1.0;
+
+// the (mode, mode) mode of a simply supported plate, a sine that is zero on the edges
+fn mode_shape(mode: usize) -> Vec<f32> {
+    let shape = |i: usize| (PI * (mode * (i + 1)) as f64 / (SIZE + 1) as f64).sin();
+    (0..SIZE * SIZE)
+        .map(|i| (shape(i % SIZE) * shape(i / SIZE)) as f32)
+        .collect()
+}
+
+fn plate(loss: f32, mode: usize) -> WaveSimulator<f64> {
+    WaveSimulator::builder()
+        .size(SIZE, SIZE)
+        .dx(DX)
+        .dt(DT)
+        .formulation(Formulation::Plate { loss: loss })
+        .space_spec(vec![(STIFFNESS, 0.0); SIZE * SIZE])
+        .field(mode_shape(mode))
+        .precision::<f64>()
+        .build()
+        .unwrap()
+}
+
+// largest value of the field over `steps`
+fn peak(plate: &mut WaveSimulator<f64>, steps: usize) -> f64 {
+    let mut peak = 0.0f64;
+    for _ in 0..steps {
+        plate.step().unwrap();
+        peak = plate.field().iter().fold(peak, |peak, v| v.abs().max(peak));
+    }
+    peak
+}
+
+#[test]
+fn a_plate_rings_at_its_mode() {
+    let mut plate = plate(0.0, 1);
+    let middle = SIZE / 2;
+
+    // times the middle crosses zero, between steps
+    let mut crossings = Vec::new();
+    let mut last = plate.get(middle, middle);
+    for t in 1..=11025 {
+        plate.step().unwrap();
+        let value = plate.get(middle, middle);
+        if (value < 0.0) != (last < 0.0) {
+            crossings.push(t as f64 - 1.0 + last / (last - value));
+        }
+        last = value;
+    }
+    let periods = (crossings.len() - 1) as f64 / 2.0;
+    let heard = periods / ((crossings[crossings.len() - 1] - crossings[0]) * DT as f64);
+
+    // the laplacian of a sine is the sine times -(2 - 2 cos(theta)) / dx^2, so the mode is
+    // the continuous one's, kappa pi^2 (2 / a^2), with 2 - 2 cos(theta) for theta^2
+    let (dx, dt) = (DX as f64, DT as f64);
+    let theta = PI / (SIZE + 1) as f64;
+    let eigenvalue = 2.0 * (2.0 - 2.0 * theta.cos()) / (dx * dx);
+    let lambda = (STIFFNESS as f64 * dt * eigenvalue).powi(2);
+    let expected = (1.0 - lambda / 2.0).acos() / dt / (2.0 * PI);
+    let continuous = STIFFNESS as f64 * PI * 2.0 / (((SIZE + 1) as f64 * dx).powi(2) * 2.0);
+
+    assert!(
+        (heard - expected).abs() < expected * 1e-4,
+        "{} Hz, not {} Hz",
+        heard,
+        expected
+    );
+    assert!((heard - continuous).abs() < continuous * 0.01);
+}
+
+#[test]
+fn loss_takes_the_high_modes_first() {
+    let low = peak(&mut plate(0.001, 1), 4410) / peak(&mut plate(0.0, 1), 4410);
+    let high = peak(&mut plate(0.001, 3), 4410) / peak(&mut plate(0.0, 3), 4410);
+
+    assert!(low < 1.0, "{}", low);
+    assert!(high < low, "{} {}", high, low);
+}
+
+#[test]
+fn bands_and_lanes_give_the_same_plate() {
+    let run = |backend: Backend, simd: bool, edge: Edge| {
+        let mut plate = WaveSimulator::builder()
+            .size(32, 24)
+            .threads(4)
+            .dx(DX)
+            .dt(DT)
+            .boundary(Boundary::all(edge))
+            .formulation(Formulation::Plate { loss: 0.001 })
+            .backend(backend)
+            .simd(simd)
+            .precision::<f64>()
+            .space_spec(vec![(STIFFNESS as f64, 1.0); 32 * 24])
+            .build()
+            .unwrap();
+        plate.add_gauss(10.0, 8.0, 2.0, 1.0);
+        plate.run(200).unwrap();
+        plate.field().to_vec()
+    };
+
+    for &edge in [Edge::Dirichlet, Edge::Neumann, Edge::Periodic].iter() {
+        let scalar = run(Backend::Serial, false, edge);
+        for &backend in [Backend::Serial, Backend::Threaded, Backend::Rayon].iter() {
+            assert_eq!(scalar, run(backend, true, edge), "{:?} {:?}", backend, edge);
+        }
+    }
+}
+
+#[test]
+fn plates_check_their_settings() {
+    let plate = WaveSimulator::builder()
+        .size(SIZE, SIZE)
+        .dx(DX)
+        .dt(DT)
+        .formulation(Formulation::Plate { loss: 0.0 })
+        .space_spec(vec![(STIFFNESS, 0.0); SIZE * SIZE]);
+    let config_error = |builder: &WaveSimulatorBuilder| match builder.build() {
+        Err(Error::Config(reason)) => reason,
+        other => panic!("{:?}", other.map(|_| ())),
+    };
+
+    config_error(&plate.stencil(Stencil::NinePoint));
+    config_error(&plate.boundary(Boundary::all(Edge::Mur1)));
+
+    // kappa dt / dx^2 is held to 1 / 4
+    let max = DX * DX / (4.0 * DT);
+    assert!((plate.build().unwrap().max_stable_speed() - max).abs() < max * 1e-4);
+    config_error(&plate.space_spec(vec![(max * 1.01, 0.0); SIZE * SIZE]));
+
+    // the default spec fits the default physics, and a slow dt says which limit it broke
+    let settings = |physics: Physics| Settings {
+        physics: physics,
+        formulation: Formulation::Plate { loss: 0.0 },
+        ..Settings::new(SIZE, SIZE)
+    };
+    WaveSimulator::<f32>::with_settings(settings(Physics::default())).unwrap();
+    let slow = Physics {
+        dt: 1.0 / 60.0,
+        ..Physics::default()
+    };
+    match WaveSimulator::<f32>::with_settings(settings(slow)) {
+        Err(Error::Config(reason)) => assert!(reason.contains("stiffness"), "{}", reason),
+        other => panic!("{:?}", other.map(|_| ())),
+    }
+}
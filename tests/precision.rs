@@ -0,0 +1,81 @@
+mod common;
+
+use audio_sim::wave_simulator::*;
+
+// the reference every other path is held to
+fn serial(settings: Settings) -> Settings {
+    Settings {
+        backend: Backend::Serial,
+        simd: false,
+        tiling: None,
+        ..settings
+    }
+}
+
+#[test]
+fn double_precision_is_the_same_on_every_path() {
+    for settings in common::scenes() {
+        let settings = serial(settings);
+        let reference = common::run::<f64>(settings);
+        assert!(reference.iter().any(|v| *v != 0.0));
+
+        let others = [
+            Settings {
+                simd: true,
+                ..settings
+            },
+            Settings {
+                simd: true,
+                backend: Backend::Threaded,
+                ..settings
+            },
+            Settings {
+                simd: true,
+                backend: Backend::Rayon,
+                tiling: Some(Tiling::new(4, 7)),
+                ..settings
+            },
+        ];
+        for &other in others.iter() {
+            for (a, b) in reference.iter().zip(common::run::<f64>(other).iter()) {
+                assert_eq!(a.to_bits(), b.to_bits(), "{:?}", other);
+            }
+        }
+    }
+}
+
+#[test]
+fn single_and_double_precision_agree() {
+    for settings in common::scenes() {
+        let settings = serial(settings);
+        let single = common::run::<f32>(settings);
+        let double = common::run::<f64>(settings);
+        assert_eq!(single.len(), double.len());
+
+        let peak = double.iter().fold(0.0f64, |peak, v| peak.max(v.abs()));
+        for (a, b) in single.iter().zip(double.iter()) {
+            assert!(
+                (*a as f64 - b).abs() < peak * 1e-3,
+                "{} and {} with {:?}",
+                a,
+                b,
+                settings
+            );
+        }
+    }
+}
+
+#[test]
+fn snapshots_keep_the_precision() {
+    let mut wave_simulator = WaveSimulator::<f64>::new(16, 12).unwrap();
+    wave_simulator.order(Order::Change(Parameter::PropagationRatio(3, 4, 0.1)));
+    wave_simulator.step().unwrap();
+
+    let snapshot: Space<f64> = wave_simulator.snapshot();
+    // the f32 order is widened, not rounded again
+    assert_eq!(
+        snapshot.space_spec[3 + 4 * 16].0.to_bits(),
+        (0.1f32 as f64).to_bits()
+    );
+    assert_eq!(snapshot.space.len(), 16 * 12);
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use audio_sim::wave_simulator::*;
+
+#[test]
+fn vector_and_scalar_paths_agree_bit_for_bit() {
+    for settings in common::scenes() {
+        let settings = Settings {
+            backend: Backend::Serial,
+            ..settings
+        };
+        let scalar: Vec<f32> = common::run(Settings {
+            simd: false,
+            ..settings
+        });
+        let vector: Vec<f32> = common::run(Settings {
+            simd: true,
+            ..settings
+        });
+        assert!(scalar.iter().any(|v| *v != 0.0));
+        for (a, b) in scalar.iter().zip(vector.iter()) {
+            assert_eq!(a.to_bits(), b.to_bits(), "{:?}", settings);
+        }
+    }
+}
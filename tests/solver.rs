@@ -0,0 +1,81 @@
+mod common;
+
+use audio_sim::wave_simulator::*;
+
+const SIZE: usize = 40;
+
+#[test]
+fn backends_agree_bit_for_bit() {
+    // every cell is computed by the same code from the same inputs, only the bands differ
+    for settings in common::scenes() {
+        let serial: Vec<f32> = common::run(Settings {
+            backend: Backend::Serial,
+            ..settings
+        });
+        assert!(serial.iter().any(|v| *v != 0.0));
+
+        for &backend in [Backend::Threaded, Backend::Rayon].iter() {
+            let other: Vec<f32> = common::run(Settings {
+                backend: backend,
+                ..settings
+            });
+            assert_eq!(serial.len(), other.len());
+            for (a, b) in serial.iter().zip(other.iter()) {
+                assert_eq!(
+                    a.to_bits(),
+                    b.to_bits(),
+                    "{:?} with {:?}",
+                    backend,
+                    settings
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn solvers_work_without_the_simulator() {
+    let settings = Settings {
+        num_threads: 2,
+        ..Settings::new(SIZE, SIZE)
+    };
+    let mut solvers: Vec<Box<dyn Solver>> = vec![
+        Box::new(SerialSolver::new(settings).unwrap()),
+        Box::new(ThreadedSolver::new(settings).unwrap()),
+        Box::new(RayonSolver::new(settings).unwrap()),
+    ];
+
+    for solver in solvers.iter_mut() {
+        solver
+            .apply(Order::Impulse(Impulse::Gauss {
+                x: 20.0,
+                y: 20.0,
+                sigma: 2.0,
+                power: 1.0,
+            }))
+            .unwrap();
+        solver.run(20).unwrap();
+        solver.step().unwrap();
+        assert_eq!(solver.current_step(), 21);
+    }
+
+    for solver in solvers.iter().skip(1) {
+        assert_eq!(solvers[0].field(), solver.field());
+    }
+    for solver in solvers {
+        solver.shutdown().unwrap();
+    }
+}
+
+#[test]
+fn every_backend_checks_the_settings() {
+    for &backend in [Backend::Serial, Backend::Threaded, Backend::Rayon].iter() {
+        let settings = Settings {
+            boundary: Boundary::all(Edge::Mur1),
+            formulation: Formulation::Staggered,
+            backend: backend,
+            ..Settings::new(SIZE, SIZE)
+        };
+        assert!(WaveSimulator::<f32>::with_settings(settings).is_err());
+    }
+}
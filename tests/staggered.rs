@@ -0,0 +1,107 @@
+use audio_sim::wave_simulator::*;
+
+const SIZE: usize = 48;
+
+fn simulator(formulation: Formulation, num_threads: usize) -> WaveSimulator {
+    let mut wave_simulator = WaveSimulator::with_settings(Settings {
+        num_threads: num_threads,
+        boundary: Boundary {
+            left: Edge::Dirichlet,
+            right: Edge::Neumann,
+            top: Edge::Periodic,
+            bottom: Edge::Periodic,
+        },
+        formulation: formulation,
+        ..Settings::new(SIZE, SIZE)
+    })
+    .unwrap();
+
+    wave_simulator.order(Order::Fill(
+        Region::Rect {
+            x: 0,
+            y: 0,
+            width: SIZE,
+            height: SIZE,
+        },
+        (wave_simulator.max_stable_speed() / 2.0, 0.0),
+    ));
+    wave_simulator.add_gauss(20.0, 30.0, 2.0, 1.0);
+    wave_simulator
+}
+
+#[test]
+fn matches_the_second_order_formulation() {
+    // without damping and with constant density both are the same scheme
+    let mut second_order = simulator(Formulation::SecondOrder, 1);
+    let mut staggered = simulator(Formulation::Staggered, 3);
+    second_order.run(300).unwrap();
+    staggered.run(300).unwrap();
+
+    let peak = second_order
+        .field()
+        .iter()
+        .fold(0.0f32, |peak, v| peak.max(v.abs()));
+    for (a, b) in second_order.field().iter().zip(staggered.field().iter()) {
+        assert!((a - b).abs() < 1e-4 * peak);
+    }
+}
+
+#[test]
+fn plane_waves_carry_pressure_and_velocity_by_impedance() {
+    // one cell high and periodic, so the waves are plane
+    let width = 400;
+    let mut wave_simulator = WaveSimulator::with_settings(Settings {
+        physics: Physics::audio(0.02, 44100),
+        boundary: Boundary {
+            top: Edge::Periodic,
+            bottom: Edge::Periodic,
+            ..Boundary::all(Edge::Neumann)
+        },
+        formulation: Formulation::Staggered,
+        ..Settings::new(width, 1)
+    })
+    .unwrap();
+
+    let air = Material::air(20.0);
+    wave_simulator.order(Order::FillMaterial(
+        Region::Rect {
+            x: 0,
+            y: 0,
+            width: width,
+            height: 1,
+        },
+        air,
+    ));
+    wave_simulator.add_gauss(100.0, 0.0, 6.0, 1.0);
+    // the pulse splits, the right half has passed x = 200 after 260 steps
+    let mut peak = (0.0f32, 0.0f32);
+    for _ in 0..300 {
+        wave_simulator.step().unwrap();
+        let pressure = wave_simulator.get(200, 0);
+        if pressure > peak.0 {
+            peak = (pressure, wave_simulator.velocity_at(200, 0).unwrap().0);
+        }
+    }
+
+    let impedance = peak.0 / peak.1;
+    assert!((impedance - air.impedance()).abs() < 0.02 * air.impedance());
+}
+
+#[test]
+fn only_the_staggered_formulation_has_velocity() {
+    assert!(simulator(Formulation::SecondOrder, 1).velocity().is_none());
+    let staggered = simulator(Formulation::Staggered, 1);
+    let (velocity_x, velocity_y) = staggered.velocity().unwrap();
+    assert_eq!(velocity_x.len(), (SIZE + 1) * SIZE);
+    assert_eq!(velocity_y.len(), SIZE * (SIZE + 1));
+}
+
+#[test]
+fn staggered_takes_no_absorbing_edges() {
+    let settings = Settings {
+        boundary: Boundary::all(Edge::Mur1),
+        formulation: Formulation::Staggered,
+        ..Settings::new(SIZE, SIZE)
+    };
+    assert!(WaveSimulator::<f32>::with_settings(settings).is_err());
+}
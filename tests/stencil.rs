@@ -0,0 +1,76 @@
+use audio_sim::wave_simulator::*;
+
+const SIZE: usize = 101;
+const CENTRE: usize = SIZE / 2;
+
+// step at which the pulse peaks, between two steps
+fn arrival(values: &[f32]) -> f32 {
+    let mut i = 1;
+    for j in 1..values.len() - 1 {
+        if values[j] > values[i] {
+            i = j;
+        }
+    }
+    let (a, b, c) = (values[i - 1], values[i], values[i + 1]);
+    i as f32 + 0.5 * (a - c) / (a - 2.0 * b + c)
+}
+
+// relative difference between the wavefront speed along an axis and along a diagonal
+fn anisotropy(stencil: Stencil) -> f32 {
+    let mut wave_simulator = WaveSimulator::with_settings(Settings {
+        stencil: stencil,
+        ..Settings::new(SIZE, SIZE)
+    })
+    .unwrap();
+
+    // a quarter of a cell per step, low enough that the error in time doesn't matter
+    let speed = wave_simulator.physics().dx / wave_simulator.physics().dt / 4.0;
+    wave_simulator.order(Order::Fill(
+        Region::Rect {
+            x: 0,
+            y: 0,
+            width: SIZE,
+            height: SIZE,
+        },
+        (speed, 0.0),
+    ));
+    // narrow enough to carry frequencies close to what the grid can hold
+    wave_simulator.add_gauss(CENTRE as f32, CENTRE as f32, 0.7, 1.0);
+
+    let (axis, diagonal) = (40, 28);
+    let mut on_axis = Vec::new();
+    let mut on_diagonal = Vec::new();
+    // the front passes both probes before anything comes back from the edges
+    for _ in 0..220 {
+        wave_simulator.step().unwrap();
+        on_axis.push(wave_simulator.get(CENTRE + axis, CENTRE));
+        on_diagonal.push(wave_simulator.get(CENTRE + diagonal, CENTRE + diagonal));
+    }
+
+    let speed_axis = axis as f32 / arrival(&on_axis);
+    let speed_diagonal = diagonal as f32 * 2.0f32.sqrt() / arrival(&on_diagonal);
+    (speed_axis - speed_diagonal).abs() / speed_axis
+}
+
+#[test]
+fn five_point_is_anisotropic() {
+    assert!(anisotropy(Stencil::FivePoint) > 0.05);
+}
+
+#[test]
+fn nine_point_is_isotropic() {
+    assert!(anisotropy(Stencil::NinePoint) < 0.005);
+}
+
+#[test]
+fn fourth_order_is_isotropic() {
+    assert!(anisotropy(Stencil::FourthOrder) < 0.005);
+}
+
+#[test]
+fn stability_limit_depends_on_the_stencil() {
+    let physics = Physics::default();
+    let five = physics.max_stable_speed(Stencil::FivePoint);
+    assert!(physics.max_stable_speed(Stencil::NinePoint) > five);
+    assert!(physics.max_stable_speed(Stencil::FourthOrder) < five);
+}
@@ -0,0 +1,141 @@
+use audio_sim::wave_simulator::*;
+
+const LENGTH: usize = 100;
+const SPEED: f32 = 200.0;
+
+// 1 cm samples at 44.1 kHz, no damping
+fn string(left: End, right: End) -> WaveSimulator1D {
+    let mut string = WaveSimulator1D::with_settings(Settings1D {
+        physics: Physics::audio(0.01, 44100),
+        left: left,
+        right: right,
+        ..Settings1D::new(LENGTH)
+    })
+    .unwrap();
+    for cell in string.space_spec_mut().iter_mut() {
+        *cell = (SPEED, 0.0);
+    }
+    string
+}
+
+fn peak(string: &WaveSimulator1D) -> f32 {
+    string.field().iter().fold(0.0, |peak, v| v.abs().max(peak))
+}
+
+// an open string may be left displaced, but still
+fn spread(string: &WaveSimulator1D) -> f32 {
+    let field = string.field();
+    let max = field.iter().cloned().fold(std::f32::MIN, f32::max);
+    let min = field.iter().cloned().fold(std::f32::MAX, f32::min);
+    max - min
+}
+
+#[test]
+fn a_plucked_string_sounds_its_fundamental() {
+    let mut string = string(End::Fixed, End::Fixed);
+    string.pluck(49.5, 1.0);
+
+    // plucked in the middle, the middle moves in a triangle wave crossing zero twice a period
+    let mut crossings = 0;
+    let mut last = string.get(50);
+    for _ in 0..44100 {
+        string.step().unwrap();
+        let value = string.get(50);
+        if (value < 0.0) != (last < 0.0) {
+            crossings += 1;
+        }
+        last = value;
+    }
+
+    // the cells outside the string are the fixed points
+    let fundamental = SPEED / (2.0 * (LENGTH + 1) as f32 * 0.01);
+    let heard = crossings as f32 / 2.0;
+    assert!(
+        (heard - fundamental).abs() < fundamental * 0.01,
+        "{} Hz, not {} Hz",
+        heard,
+        fundamental
+    );
+}
+
+#[test]
+fn open_ends_let_the_waves_out() {
+    let mut held = string(End::Fixed, End::Free);
+    let mut open = string(End::Open, End::Open);
+    for string in [&mut held, &mut open].iter_mut() {
+        string.pluck(30.0, 1.0);
+        // four times along the string
+        string
+            .run(4 * 44100 * LENGTH / 100 / SPEED as usize)
+            .unwrap();
+    }
+
+    assert!(spread(&held) > 0.5, "{}", spread(&held));
+    assert!(spread(&open) < 0.01, "{}", spread(&open));
+}
+
+#[test]
+fn a_bow_keeps_a_damped_string_going() {
+    let mut plucked = string(End::Fixed, End::Fixed);
+    let mut bowed = string(End::Fixed, End::Fixed);
+    for string in [&mut plucked, &mut bowed].iter_mut() {
+        for cell in string.space_spec_mut().iter_mut() {
+            cell.1 = 20.0;
+        }
+    }
+    plucked.pluck(20.0, 0.01);
+    bowed
+        .add_bow(Bow {
+            position: 20,
+            velocity: 0.1,
+            force: 5000.0,
+        })
+        .unwrap();
+
+    plucked.run(44100).unwrap();
+    bowed.run(44100).unwrap();
+
+    assert!(bowed.field().iter().all(|v| v.is_finite()));
+    assert!(peak(&plucked) < 1e-5, "{}", peak(&plucked));
+    assert!(peak(&bowed) > 1e-5, "{}", peak(&bowed));
+}
+
+#[test]
+fn bows_stay_on_the_string() {
+    let mut string = string(End::Fixed, End::Fixed);
+    let bow = Bow {
+        position: LENGTH,
+        velocity: 0.1,
+        force: 1.0,
+    };
+
+    match string.add_bow(bow) {
+        Err(Error::Config(_)) => (),
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn a_string_runs_at_courant_1() {
+    // the guitar string of main, 200 samples of 65 cm
+    let mut string = WaveSimulator1D::<f64>::with_settings(Settings1D {
+        physics: Physics::audio(0.65 / 200.0, 44100),
+        ..Settings1D::new(200)
+    })
+    .unwrap();
+    assert!((133.0f32 / 0.0065).sqrt() <= string.max_stable_speed());
+
+    // at dx / dt a wave moves one sample a step, so a period is 2 (length + 1) steps.
+    // dx / dt is rounded to f32, so it comes back close rather than exact
+    let speed = string.max_stable_speed() as f64;
+    for cell in string.space_spec_mut().iter_mut() {
+        *cell = (speed, 0.0);
+    }
+    string.pluck(50.0, 1.0);
+    let plucked = string.field().to_vec();
+    string.run(2 * 201).unwrap();
+
+    for (x, (now, then)) in string.field().iter().zip(plucked.iter()).enumerate() {
+        assert!((now - then).abs() < 1e-5, "{}: {} {}", x, now, then);
+    }
+}
@@ -0,0 +1,32 @@
+use audio_sim::wave_simulator::*;
+
+// threads of this process, the only test of the file runs alone with the main thread
+#[cfg(target_os = "linux")]
+fn threads() -> usize {
+    std::fs::read_dir("/proc/self/task").unwrap().count()
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn drops_join_the_workers() {
+    let settings = Settings {
+        num_threads: 3,
+        ..Settings::new(24, 18)
+    };
+    let before = threads();
+
+    let mut solver = ThreadedSolver::<f32>::new(settings).unwrap();
+    assert_eq!(threads(), before + 3);
+    solver.run(5).unwrap();
+    drop(solver);
+    assert_eq!(threads(), before);
+
+    let wave_simulator = WaveSimulator::<f32>::with_settings(Settings {
+        backend: Backend::Threaded,
+        ..settings
+    })
+    .unwrap();
+    assert!(threads() > before);
+    drop(wave_simulator);
+    assert_eq!(threads(), before);
+}
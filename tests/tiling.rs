@@ -0,0 +1,53 @@
+mod common;
+
+use audio_sim::wave_simulator::*;
+
+#[test]
+fn tiled_runs_match_step_by_step() {
+    let tilings = [
+        Tiling::new(4, 5),
+        Tiling::new(7, 1),
+        Tiling::new(16, 64),
+        Tiling::for_width(common::WIDTH),
+    ];
+
+    // a periodic top and bottom and the staggered grid can't be tiled, they run step by step
+    for settings in common::scenes() {
+        let plain: Vec<f32> = common::run(Settings {
+            backend: Backend::Serial,
+            ..settings
+        });
+        assert!(plain.iter().any(|v| *v != 0.0));
+
+        for &tiling in tilings.iter() {
+            for &backend in [Backend::Serial, Backend::Threaded, Backend::Rayon].iter() {
+                let tiled: Vec<f32> = common::run(Settings {
+                    tiling: Some(tiling),
+                    backend: backend,
+                    ..settings
+                });
+                for (a, b) in plain.iter().zip(tiled.iter()) {
+                    assert_eq!(
+                        a.to_bits(),
+                        b.to_bits(),
+                        "{:?} on {:?} with {:?}",
+                        tiling,
+                        backend,
+                        settings
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn empty_tiles_are_rejected() {
+    for &tiling in [Tiling::new(0, 8), Tiling::new(8, 0)].iter() {
+        let settings = Settings {
+            tiling: Some(tiling),
+            ..Settings::new(common::WIDTH, common::HEIGHT)
+        };
+        assert!(WaveSimulator::<f32>::with_settings(settings).is_err());
+    }
+}
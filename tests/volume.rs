@@ -0,0 +1,294 @@
+use audio_sim::wave_simulator::*;
+
+const SIZE: usize = 17;
+
+fn cube(num_threads: usize, stencil: Stencil3D, boundary: Boundary3D) -> WaveSimulator3D {
+    let mut wave_simulator = WaveSimulator3D::with_settings(Settings3D {
+        num_threads: num_threads,
+        physics: Physics::audio(0.02, 44100),
+        boundary: boundary,
+        stencil: stencil,
+        ..Settings3D::new(SIZE, SIZE, SIZE)
+    })
+    .unwrap();
+
+    let speed = wave_simulator.max_stable_speed() * 0.9;
+    for spec in wave_simulator.space_spec_mut() {
+        *spec = (speed, 5.0);
+    }
+    wave_simulator.add_gauss(8.0, 8.0, 8.0, 1.5, 1.0);
+    wave_simulator
+}
+
+#[test]
+fn waves_spread_the_same_way_along_every_axis() {
+    for &stencil in [Stencil3D::SevenPoint, Stencil3D::FourthOrder].iter() {
+        for &edge in [Edge::Dirichlet, Edge::Neumann, Edge::Periodic].iter() {
+            let mut wave_simulator = cube(2, stencil, Boundary3D::all(edge));
+            wave_simulator.run(30).unwrap();
+            assert!(wave_simulator.field().iter().any(|v| *v != 0.0));
+
+            for z in 0..SIZE {
+                for y in 0..SIZE {
+                    for x in 0..SIZE {
+                        let value = wave_simulator.get(x, y, z);
+                        for &(a, b, c) in [(y, x, z), (z, y, x), (x, z, y)].iter() {
+                            let other = wave_simulator.get(a, b, c);
+                            assert!((value - other).abs() <= 1e-6, "{:?} {:?}", stencil, edge);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn slabs_give_the_same_field() {
+    let boundary = Boundary3D {
+        front: Edge::Periodic,
+        back: Edge::Periodic,
+        ..Boundary3D::all(Edge::Neumann)
+    };
+    let reference = {
+        let mut wave_simulator = cube(1, Stencil3D::FourthOrder, boundary);
+        wave_simulator.run(25).unwrap();
+        wave_simulator.field().to_vec()
+    };
+
+    for &num_threads in [2, 3, 5, 40].iter() {
+        let mut wave_simulator = cube(num_threads, Stencil3D::FourthOrder, boundary);
+        wave_simulator.run(10).unwrap();
+        wave_simulator.run(15).unwrap();
+        assert_eq!(wave_simulator.field(), &reference[..], "{}", num_threads);
+    }
+}
+
+// a single periodic plane has nothing changing along z, it is the 2d simulator
+#[test]
+fn one_periodic_plane_is_the_plane() {
+    let stencils = [
+        (Stencil3D::SevenPoint, Stencil::FivePoint),
+        (Stencil3D::FourthOrder, Stencil::FourthOrder),
+    ];
+    for &(stencil3d, stencil) in stencils.iter() {
+        let physics = Physics::audio(0.02, 44100);
+        let mut volume = WaveSimulator3D::<f64>::with_settings(Settings3D {
+            physics: physics,
+            boundary: Boundary3D {
+                front: Edge::Periodic,
+                back: Edge::Periodic,
+                ..Boundary3D::all(Edge::Neumann)
+            },
+            stencil: stencil3d,
+            ..Settings3D::new(30, 20, 1)
+        })
+        .unwrap();
+        let mut plane = WaveSimulator::<f64>::with_settings(Settings {
+            physics: physics,
+            boundary: Boundary::all(Edge::Neumann),
+            stencil: stencil,
+            ..Settings::new(30, 20)
+        })
+        .unwrap();
+
+        let speed = volume.max_stable_speed() * 0.9;
+        for spec in volume.space_spec_mut() {
+            *spec = (speed as f64, 1.0);
+        }
+        for spec in plane.space_spec_mut() {
+            *spec = (speed as f64, 1.0);
+        }
+        volume.add_gauss(12.0, 9.0, 0.0, 2.0, 1.0);
+        plane.add_gauss(12.0, 9.0, 2.0, 1.0);
+        volume.run(60).unwrap();
+        plane.run(60).unwrap();
+
+        for (a, b) in volume.field().iter().zip(plane.field().iter()) {
+            assert!((a - b).abs() < 1e-9, "{} and {} with {:?}", a, b, stencil3d);
+        }
+    }
+}
+
+#[test]
+fn slices_cut_the_volume() {
+    let mut wave_simulator = WaveSimulator3D::<f32>::new(5, 4, 3).unwrap();
+    // every cell holds its index, so the slices are compared bit for bit
+    for (i, value) in wave_simulator.field_mut().iter_mut().enumerate() {
+        *value = i as f32;
+    }
+
+    let slice = wave_simulator.slice(Axis::Z, 2);
+    assert_eq!((slice.width, slice.height), (5, 4));
+    assert_eq!(
+        slice.get(3, 1).to_bits(),
+        wave_simulator.get(3, 1, 2).to_bits()
+    );
+
+    let slice = wave_simulator.slice(Axis::X, 4);
+    assert_eq!((slice.width, slice.height), (3, 4));
+    assert_eq!(
+        slice.get(2, 1).to_bits(),
+        wave_simulator.get(4, 1, 2).to_bits()
+    );
+
+    let slice = wave_simulator.snapshot().slice(Axis::Y, 3);
+    assert_eq!((slice.width, slice.height), (5, 3));
+    assert_eq!(
+        slice.get(1, 2).to_bits(),
+        wave_simulator.get(1, 3, 2).to_bits()
+    );
+}
+
+#[test]
+fn orders_land_on_a_plane() {
+    let mut wave_simulator = WaveSimulator3D::<f32>::new(6, 5, 4).unwrap();
+    let region = Region::Rect {
+        x: 1,
+        y: 1,
+        width: 2,
+        height: 2,
+    };
+    let speed = wave_simulator.max_stable_speed() / 2.0;
+    let untouched = wave_simulator.space_spec()[0];
+    wave_simulator
+        .apply(2, Order::Fill(region, (speed, 3.0)))
+        .unwrap();
+    wave_simulator
+        .apply(
+            2,
+            Order::Impulse(Impulse::Point {
+                x: 4.0,
+                y: 3.0,
+                power: 1.0,
+            }),
+        )
+        .unwrap();
+
+    let spec = wave_simulator.space_spec();
+    assert_eq!(spec[2 + 6 + 2 * 30], (speed, 3.0));
+    assert_eq!(spec[2 + 6 + 30], untouched);
+    assert!(wave_simulator.get(4, 3, 2) > 0.5);
+    assert!(wave_simulator.get(4, 3, 1).abs() < 1e-6);
+
+    wave_simulator.apply(0, Order::Reset).unwrap();
+    assert!(wave_simulator.field().iter().all(|v| *v == 0.0));
+
+    // what a volume can't do is refused, not dropped
+    let refused = [
+        (2, Order::Change(Parameter::Density(1, 1, 2.0))),
+        (
+            2,
+            Order::Change(Parameter::PropagationRatio(1, 1, speed * 4.0)),
+        ),
+        (2, Order::Pause),
+        (4, Order::Reset),
+    ];
+    for (z, order) in refused.iter().cloned() {
+        assert!(matches!(
+            wave_simulator.apply(z, order),
+            Err(Error::Config(_))
+        ));
+    }
+    assert_eq!(wave_simulator.space_spec()[1 + 6 + 2 * 30], (speed, 3.0));
+}
+
+#[test]
+fn volumes_check_their_settings() {
+    let settings = [
+        Settings3D::new(8, 8, 0),
+        Settings3D {
+            num_threads: 0,
+            ..Settings3D::new(8, 8, 8)
+        },
+        Settings3D {
+            boundary: Boundary3D::all(Edge::Mur1),
+            ..Settings3D::new(8, 8, 8)
+        },
+        Settings3D {
+            boundary: Boundary3D::all(Edge::Pml { thickness: 2 }),
+            ..Settings3D::new(8, 8, 8)
+        },
+        Settings3D {
+            boundary: Boundary3D {
+                front: Edge::Periodic,
+                ..Boundary3D::default()
+            },
+            ..Settings3D::new(8, 8, 8)
+        },
+        Settings3D {
+            watch: Some(Watch {
+                window: 0,
+                ..Watch::default()
+            }),
+            ..Settings3D::new(8, 8, 8)
+        },
+    ];
+    for &settings in settings.iter() {
+        assert!(matches!(
+            WaveSimulator3D::<f32>::with_settings(settings),
+            Err(Error::Config(_))
+        ));
+    }
+}
+
+#[test]
+fn volumes_say_which_cell_is_unstable() {
+    // too coarse a dt for air
+    let settings = Settings3D {
+        physics: Physics::audio(0.001, 44100),
+        ..Settings3D::new(4, 3, 2)
+    };
+    match WaveSimulator3D::<f32>::with_settings(settings) {
+        Err(Error::Config(reason)) => assert!(reason.contains("(0, 0, 0)"), "{}", reason),
+        other => panic!("{:?}", other.map(|_| ())),
+    }
+
+    // finite, but too loud to square in f32
+    let mut wave_simulator = cube(3, Stencil3D::SevenPoint, Boundary3D::default());
+    wave_simulator.field_mut()[3 + 4 * SIZE + 11 * SIZE * SIZE] = 1e20;
+    match wave_simulator.step() {
+        Err(Error::Unstable(instability)) => {
+            assert_eq!(
+                (instability.x, instability.y, instability.z),
+                (3, 4, Some(11))
+            );
+            assert!(instability.to_string().contains("(3, 4, 11)"));
+        }
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn volumes_stop_when_they_blow_up() {
+    let mut wave_simulator = cube(3, Stencil3D::FourthOrder, Boundary3D::default());
+    wave_simulator.run(20).unwrap();
+    let speed = wave_simulator.max_stable_speed() * 4.0;
+    for z in 10..14 {
+        for y in 2..6 {
+            for x in 4..8 {
+                wave_simulator.space_spec_mut()[x + y * SIZE + z * SIZE * SIZE].0 = speed;
+            }
+        }
+    }
+
+    let instability = match wave_simulator.run(2000) {
+        Err(Error::Unstable(instability)) => instability,
+        other => panic!("{:?}", other.map(|_| ())),
+    };
+    assert!(instability.step > 20 && instability.step <= 2000);
+    assert!(instability.x < SIZE && instability.y < SIZE);
+    assert!(instability.z.map_or(false, |z| z < SIZE));
+    assert_eq!(wave_simulator.current_step(), instability.step);
+
+    // until the field is reset
+    assert!(wave_simulator.step().is_err());
+    assert!(wave_simulator.run(0).is_err());
+    let speed = wave_simulator.max_stable_speed() / 2.0;
+    for spec in wave_simulator.space_spec_mut() {
+        spec.0 = speed;
+    }
+    wave_simulator.apply(0, Order::Reset).unwrap();
+    wave_simulator.run(50).unwrap();
+    assert!(wave_simulator.field().iter().all(|v| *v == 0.0));
+}
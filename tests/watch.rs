@@ -0,0 +1,170 @@
+use audio_sim::wave_simulator::*;
+
+const WIDTH: usize = 40;
+const HEIGHT: usize = 30;
+
+fn simulator(backend: Backend, watch: Option<Watch>) -> WaveSimulator {
+    let mut wave_simulator = WaveSimulator::with_settings(Settings {
+        backend: backend,
+        num_threads: 3,
+        watch: watch,
+        ..Settings::new(WIDTH, HEIGHT)
+    })
+    .unwrap();
+    wave_simulator.add_gauss(12.0, 15.0, 2.0, 1.0);
+    wave_simulator
+}
+
+// orders over the cfl limit are refused, the spec itself isn't checked
+fn make_unstable(wave_simulator: &mut WaveSimulator) {
+    let speed = wave_simulator.max_stable_speed() * 4.0;
+    for y in 10..16 {
+        for x in 25..31 {
+            wave_simulator.space_spec_mut()[x + y * WIDTH].0 = speed;
+        }
+    }
+}
+
+#[test]
+fn stable_runs_report_their_energy() {
+    for &backend in [Backend::Serial, Backend::Threaded, Backend::Rayon].iter() {
+        let mut wave_simulator = simulator(backend, Some(Watch::default()));
+        assert_eq!(wave_simulator.stats(), None);
+
+        wave_simulator.run(300).unwrap();
+        let stats = wave_simulator.stats().unwrap();
+        assert_eq!(stats.step, 300);
+        assert!(stats.energy > 0.0 && stats.energy.is_finite());
+
+        let (x, y) = stats.peak_at;
+        assert_eq!(
+            (wave_simulator.get(x, y).abs() as f64).to_bits(),
+            stats.peak.to_bits()
+        );
+        wave_simulator.shutdown().unwrap();
+    }
+}
+
+#[test]
+fn blow_ups_stop_the_run() {
+    for &backend in [Backend::Serial, Backend::Threaded, Backend::Rayon].iter() {
+        let mut wave_simulator = simulator(backend, Some(Watch::default()));
+        wave_simulator.run(20).unwrap();
+        make_unstable(&mut wave_simulator);
+
+        let instability = match wave_simulator.run(2000) {
+            Err(Error::Unstable(instability)) => instability,
+            other => panic!("{:?} on {:?}", other.map(|_| ()), backend),
+        };
+        assert!(instability.step > 20 && instability.step <= 2000);
+        assert!(instability.x < WIDTH && instability.y < HEIGHT);
+        assert!(wave_simulator.current_step() < instability.step + Watch::default().window);
+
+        // until the field is reset
+        assert!(wave_simulator.step().is_err());
+        assert!(wave_simulator.run(0).is_err());
+        let speed = wave_simulator.max_stable_speed() / 2.0;
+        wave_simulator.order(Order::Fill(
+            Region::Rect {
+                x: 0,
+                y: 0,
+                width: WIDTH,
+                height: HEIGHT,
+            },
+            (speed, 0.0),
+        ));
+        wave_simulator.order(Order::Reset);
+        wave_simulator.run(50).unwrap();
+        assert!(wave_simulator.field().iter().all(|v| *v == 0.0));
+        wave_simulator.shutdown().unwrap();
+    }
+}
+
+#[test]
+fn not_finite_cells_are_found() {
+    for &backend in [Backend::Serial, Backend::Threaded, Backend::Rayon].iter() {
+        let mut wave_simulator = simulator(backend, Some(Watch::default()));
+        wave_simulator.run(10).unwrap();
+        wave_simulator.field_mut()[33 + 21 * WIDTH] = std::f32::NAN;
+
+        match wave_simulator.step() {
+            Err(Error::Unstable(Instability {
+                step: 11,
+                cause: Cause::NotFinite,
+                ..
+            })) => (),
+            other => panic!("{:?} on {:?}", other, backend),
+        }
+        wave_simulator.shutdown().unwrap();
+    }
+}
+
+#[test]
+fn slow_growth_adds_up() {
+    // negative damping, the energy grows about ten times a window
+    let watch = Watch {
+        window: 64,
+        growth: 100.0,
+    };
+    let mut wave_simulator = simulator(Backend::Serial, Some(watch));
+    for cell in wave_simulator.space_spec_mut().iter_mut() {
+        cell.1 = -800.0;
+    }
+
+    match wave_simulator.run(20000) {
+        Err(Error::Unstable(Instability {
+            cause: Cause::Growth(factor),
+            ..
+        })) => assert!(factor > 100.0 && factor.is_finite(), "{}", factor),
+        other => panic!("{:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn overflowing_energy_is_found_at_its_peak() {
+    // finite, but too loud to square in f32
+    let mut wave_simulator = simulator(Backend::Serial, Some(Watch::default()));
+    wave_simulator.field_mut()[33 + 21 * WIDTH] = 1e20;
+
+    match wave_simulator.step() {
+        Err(Error::Unstable(instability)) => {
+            assert_eq!((instability.x, instability.y), (33, 21));
+            match instability.cause {
+                Cause::Growth(factor) => assert!(factor.is_infinite()),
+                cause => panic!("{:?}", cause),
+            }
+        }
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn unwatched_fields_run_on() {
+    let mut wave_simulator = simulator(Backend::Serial, None);
+    make_unstable(&mut wave_simulator);
+    wave_simulator.run(2000).unwrap();
+
+    assert_eq!(wave_simulator.stats(), None);
+    assert!(wave_simulator.field().iter().any(|v| !v.is_finite()));
+}
+
+#[test]
+fn invalid_watches_are_rejected() {
+    let watches = [
+        Watch {
+            window: 0,
+            ..Watch::default()
+        },
+        Watch {
+            growth: 1.0,
+            ..Watch::default()
+        },
+    ];
+    for &watch in watches.iter() {
+        let settings = Settings {
+            watch: Some(watch),
+            ..Settings::new(WIDTH, HEIGHT)
+        };
+        assert!(WaveSimulator::<f32>::with_settings(settings).is_err());
+    }
+}